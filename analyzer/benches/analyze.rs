@@ -0,0 +1,197 @@
+use analyzer::{analyze, analyze_frame, load_references, Classifier, Frame, RegionCache, SCREENS};
+use criterion::{BatchSize, BenchmarkId, Criterion};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const INPUTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs");
+const OUTPUTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/outputs");
+
+// the references are only loaded once in a process, so loading them is timed
+// in a fresh one, which is this bench run again with this set
+const COLD_START: &str = "KART_BENCH_COLD_START";
+// how many times each frame is repeated in the stream bench, as if the screen
+// was held for a moment
+const HELD: usize = 3;
+
+/// The first frame of each screen in the golden corpus, by the event it's
+/// expected to come out as
+fn corpus() -> BTreeMap<String, image::DynamicImage> {
+    let mut paths: Vec<_> = std::fs::read_dir(INPUTS)
+        .expect("couldn't open the inputs dir")
+        .filter_map(|p| p.ok())
+        .map(|p| p.path())
+        .collect();
+    paths.sort();
+
+    let mut frames = BTreeMap::new();
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let Some(expected) = std::fs::read_to_string(format!("{OUTPUTS}/{name}.json"))
+            .ok()
+            .and_then(|e| serde_json::from_str::<Value>(&e).ok())
+        else {
+            continue;
+        };
+        let Some(screen) = expected["name"].as_str() else {
+            continue;
+        };
+
+        if !frames.contains_key(screen) {
+            let frame = image::open(&path).expect("couldn't open a corpus frame");
+            frames.insert(screen.to_string(), frame);
+        }
+    }
+
+    frames
+}
+
+fn bench_analyze(c: &mut Criterion, frames: &BTreeMap<String, image::DynamicImage>) {
+    let mut group = c.benchmark_group("analyze");
+    for (screen, frame) in frames {
+        group.bench_with_input(BenchmarkId::from_parameter(screen), frame, |b, frame| {
+            b.iter(|| analyze(frame))
+        });
+    }
+    group.finish();
+
+    // the part of `analyze` that's the same whichever screen it is
+    let race = &frames["race_screen"];
+    c.bench_function("prepare", |b| b.iter(|| Frame::new(race)));
+}
+
+/// A copy of an already prepared frame, so nothing it's worked out is kept
+/// from one iteration to the next
+fn fresh(frame: &Frame) -> Frame {
+    Frame::from(frame.image().clone())
+}
+
+/// Each detector on its own screen, where the corpus has one, and on a race
+/// frame, which is what they're mostly given
+fn bench_detectors(c: &mut Criterion, frames: &BTreeMap<String, image::DynamicImage>) {
+    let prepared: BTreeMap<_, _> = frames
+        .iter()
+        .map(|(screen, frame)| (screen.as_str(), Frame::new(frame)))
+        .collect();
+    let race = &prepared["race_screen"];
+
+    let mut compare = c.benchmark_group("compare");
+    for (name, detector, ..) in SCREENS {
+        compare.bench_with_input(BenchmarkId::new(name, "race_screen"), race, |b, frame| {
+            b.iter_batched_ref(
+                || fresh(frame),
+                |frame| detector(frame),
+                BatchSize::LargeInput,
+            )
+        });
+
+        let screen = format!("{name}_screen");
+        if let Some(frame) = prepared.get(screen.as_str()).filter(|_| name != "race") {
+            compare.bench_with_input(BenchmarkId::new(name, &screen), frame, |b, frame| {
+                b.iter_batched_ref(
+                    || fresh(frame),
+                    |frame| detector(frame),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    compare.finish();
+
+    let mut process = c.benchmark_group("process");
+    for (name, _, processor, _) in SCREENS {
+        let screen = format!("{name}_screen");
+        if let Some(frame) = prepared.get(screen.as_str()) {
+            process.bench_with_input(BenchmarkId::from_parameter(name), frame, |b, frame| {
+                b.iter_batched_ref(
+                    || fresh(frame),
+                    |frame| processor(frame),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+
+    // the same race frame again, so everything it reads is already cached
+    let (_, _, race_process, _) = SCREENS.iter().find(|(n, ..)| *n == "race").unwrap();
+    let cache = Arc::new(RegionCache::new());
+    race_process(&fresh(race).with_cache(&cache));
+    process.bench_function("race_cached", |b| {
+        b.iter_batched_ref(
+            || fresh(race).with_cache(&cache),
+            |frame| race_process(frame),
+            BatchSize::LargeInput,
+        )
+    });
+    process.finish();
+}
+
+/// Each screen in turn, held for a few frames, through `analyze_frame` and
+/// through a `Classifier`
+fn bench_stream(c: &mut Criterion, frames: &BTreeMap<String, image::DynamicImage>) {
+    let prepared: Vec<_> = frames.values().map(Frame::new).collect();
+    let stream = || {
+        prepared
+            .iter()
+            .flat_map(|frame| std::iter::repeat_n(frame, HELD))
+    };
+
+    let mut group = c.benchmark_group("stream");
+    group.bench_function("analyze_frame", |b| {
+        b.iter(|| stream().map(analyze_frame).count())
+    });
+    group.bench_function("classifier", |b| {
+        b.iter(|| {
+            let mut classifier = Classifier::new();
+            stream().map(|frame| classifier.analyze(frame)).count()
+        })
+    });
+    group.finish();
+}
+
+fn bench_cold_start(c: &mut Criterion) {
+    let bench = std::env::current_exe().expect("couldn't find the bench binary");
+
+    let mut group = c.benchmark_group("cold_start");
+    group.sample_size(10);
+    group.bench_function("load_references", |b| {
+        b.iter_custom(|iters| {
+            (0..iters)
+                .map(|_| {
+                    let output = Command::new(&bench)
+                        .env(COLD_START, "1")
+                        .output()
+                        .expect("couldn't run the bench binary");
+                    let nanos = String::from_utf8_lossy(&output.stdout)
+                        .trim()
+                        .parse()
+                        .expect("the cold start didn't report how long it took");
+
+                    Duration::from_nanos(nanos)
+                })
+                .sum()
+        })
+    });
+    group.finish();
+}
+
+fn main() {
+    if std::env::var_os(COLD_START).is_some() {
+        let start = Instant::now();
+        load_references();
+        println!("{}", start.elapsed().as_nanos());
+        return;
+    }
+
+    let frames = corpus();
+    let mut c = Criterion::default().configure_from_args();
+
+    bench_analyze(&mut c, &frames);
+    bench_detectors(&mut c, &frames);
+    bench_stream(&mut c, &frames);
+    bench_cold_start(&mut c);
+
+    c.final_summary();
+}
@@ -0,0 +1,49 @@
+// The labelled corpus the golden test runs over - frames in `tests/inputs`,
+// and the JSON we expect from each of them in `tests/outputs`. Shared by the
+// tools that work with it, which don't all need every part of it.
+#![allow(dead_code)]
+
+use analyzer::Screen;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+pub const INPUTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs");
+pub const OUTPUTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/outputs");
+
+#[derive(Serialize)]
+struct Event<'a, T: Serialize> {
+    name: &'a str,
+    body: T,
+}
+
+/// Every frame in `inputs`, in name order
+pub fn frames(inputs: &Path) -> Vec<PathBuf> {
+    let paths = std::fs::read_dir(inputs).expect("couldn't open the inputs dir");
+    let mut paths: Vec<_> = paths.filter_map(|p| p.ok()).map(|p| p.path()).collect();
+    paths.sort();
+
+    paths
+}
+
+/// Where the expectation for `frame` is kept
+pub fn expectation_path(outputs: &Path, frame: &Path) -> PathBuf {
+    let name = frame.file_name().expect("frames are files");
+
+    outputs.join(Path::new(name).with_extension("json"))
+}
+
+/// What `analyze` made of a frame, written the same way as the expectations
+pub fn render(screen: &Option<Screen>) -> String {
+    let json = match screen {
+        Some(screen) => serde_json::to_string_pretty(&Event {
+            name: screen.event_type(),
+            body: screen,
+        }),
+        None => serde_json::to_string_pretty(&Event::<Option<String>> {
+            name: "null screen",
+            body: None,
+        }),
+    };
+
+    json.expect("couldn't serialize the screen")
+}
@@ -0,0 +1,112 @@
+use analyzer::{reference_region, reference_regions, ReferenceRegion, REFERENCES_PATH};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+/// Cut a new reference image out of screenshots. Any region in the layout
+/// table can be cut, for any player's part of the screen, and it's prepared
+/// the same way the detector prepares it. References are saved into the
+/// references directory as `<name>.<ext>`, or `<name>_1.<ext>`, `<name>_2.<ext>`
+/// and so on when there's already one by that name.
+#[derive(Parser, Debug)]
+struct Args {
+    /// The region to cut, e.g. `item` or `position`. See `--list`
+    #[arg(long, required_unless_present = "list")]
+    region: Option<String>,
+
+    /// Whose part of the screen to cut from, for regions there's one of for
+    /// each player
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=4))]
+    player: u8,
+
+    /// What the reference is of, which is what it's named. e.g. `banana` for an
+    /// item, or `pos3` for a position
+    #[arg(long, required_unless_present = "list")]
+    name: Option<String>,
+
+    /// List the regions that can be cut, and where they're saved
+    #[arg(long)]
+    list: bool,
+
+    /// Screenshots to cut the reference from
+    #[arg(required_unless_present = "list")]
+    files: Vec<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if args.list {
+        list();
+        return;
+    }
+
+    let (Some(region), Some(name)) = (args.region.as_deref(), args.name.as_deref()) else {
+        unreachable!("clap requires both without --list");
+    };
+    let Some(region) = reference_region(region) else {
+        eprintln!("ERROR: there's no region called '{region}'. These are:");
+        list();
+        std::process::exit(1);
+    };
+
+    args.files.iter().for_each(|path| {
+        let image = match image::open(path) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("ERROR: Couldn't open image at {path:?} - {e}");
+                return;
+            }
+        };
+
+        let Some(reference) = region.cut(&image, args.player as usize - 1) else {
+            eprintln!(
+                "ERROR: there's no player {} for {}",
+                args.player, region.name
+            );
+            return;
+        };
+
+        let output_location = get_output_location(&region, name);
+        if let Err(e) = reference.save(&output_location) {
+            eprintln!("Failed to save new {} reference - {e}", region.name);
+            return;
+        }
+
+        let relative = output_location
+            .strip_prefix(REFERENCES_PATH)
+            .unwrap_or(&output_location);
+        println!("{path:?} -> {output_location:?}");
+        println!("    load_reference_hash!(\"{}\")", relative.display());
+    });
+}
+
+fn list() {
+    for region in reference_regions() {
+        println!(
+            "{:<10} {:<8} {:<18} references/{}/*.{}",
+            region.name,
+            if region.per_player() {
+                "player"
+            } else {
+                "screen"
+            },
+            format!("{:?}", region.preprocess),
+            region.directory,
+            region.extension
+        );
+    }
+}
+
+/// The first name that isn't taken yet
+fn get_output_location(region: &ReferenceRegion, name: &str) -> PathBuf {
+    let directory = Path::new(REFERENCES_PATH).join(region.directory);
+
+    (0..)
+        .map(|i| match i {
+            0 => format!("{name}.{}", region.extension),
+            i => format!("{name}_{i}.{}", region.extension),
+        })
+        .map(|file_name| directory.join(file_name))
+        .find(|path| !path.exists())
+        .unwrap()
+}
@@ -0,0 +1,376 @@
+use analyzer::analyze;
+use clap::Parser;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+mod corpus;
+
+/// Runs the analyzer over a labelled corpus - images alongside the JSON we
+/// expect for them - and reports how accurate it is, for the screen type and
+/// for the fields within each screen
+#[derive(Parser, Debug)]
+struct Args {
+    /// Directory of input frames
+    #[arg(long, default_value = corpus::INPUTS)]
+    inputs: PathBuf,
+
+    /// Directory of expected outputs, named the same as the inputs but with a
+    /// `.json` extension
+    #[arg(long, default_value = corpus::OUTPUTS)]
+    outputs: PathBuf,
+
+    /// Where to write the report as JSON
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// A report from an earlier run to compare this one against
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+}
+
+/// The fields we score, and the keys they're found under in the output
+const FIELDS: [(&str, &[&str]); 3] = [
+    ("position", &["position"]),
+    ("item", &["item"]),
+    ("course", &["course", "course_name"]),
+];
+
+// stands in for a field (or screen) that isn't there
+const MISSING: &str = "-";
+
+/// How often each expected label came out as each actual label
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Confusion {
+    counts: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Score {
+    precision: f64,
+    recall: f64,
+    support: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Metric {
+    accuracy: f64,
+    labels: BTreeMap<String, Score>,
+    confusion: Confusion,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Report {
+    frames: usize,
+    metrics: BTreeMap<String, Metric>,
+    /// Frames whose output doesn't match the expectation in some way
+    mismatches: Vec<String>,
+}
+
+impl Confusion {
+    fn add(&mut self, expected: &str, actual: &str) {
+        *self
+            .counts
+            .entry(expected.into())
+            .or_default()
+            .entry(actual.into())
+            .or_default() += 1;
+    }
+
+    fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .counts
+            .iter()
+            .flat_map(|(expected, actual)| {
+                std::iter::once(expected.clone()).chain(actual.keys().cloned())
+            })
+            .collect();
+        labels.sort();
+        labels.dedup();
+
+        labels
+    }
+
+    fn count(&self, expected: &str, actual: &str) -> usize {
+        self.counts
+            .get(expected)
+            .and_then(|actual_counts| actual_counts.get(actual))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn into_metric(self) -> Metric {
+        let labels = self.labels();
+        let total: usize = self.counts.values().flat_map(|c| c.values()).sum();
+        let correct: usize = labels.iter().map(|l| self.count(l, l)).sum();
+
+        let scores = labels
+            .iter()
+            .map(|label| {
+                let hits = self.count(label, label) as f64;
+                let expected: usize = labels.iter().map(|l| self.count(label, l)).sum();
+                let predicted: usize = labels.iter().map(|l| self.count(l, label)).sum();
+
+                let score = Score {
+                    precision: ratio(hits, predicted),
+                    recall: ratio(hits, expected),
+                    support: expected,
+                };
+
+                (label.clone(), score)
+            })
+            .collect();
+
+        Metric {
+            accuracy: ratio(correct as f64, total),
+            labels: scores,
+            confusion: self,
+        }
+    }
+}
+
+fn ratio(n: f64, total: usize) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        n / total as f64
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let results: Vec<_> = corpus::frames(&args.inputs)
+        .par_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let expectation_path = corpus::expectation_path(&args.outputs, path);
+
+            let Ok(expectation) = std::fs::read_to_string(&expectation_path) else {
+                eprintln!("WARNING: no expectation for {path:?}, skipping");
+                return None;
+            };
+            let expected: Value = match serde_json::from_str(&expectation) {
+                Ok(e) => e,
+                Err(e) => {
+                    eprintln!("ERROR: couldn't parse {expectation_path:?} - {e}");
+                    return None;
+                }
+            };
+            let image = match image::open(path) {
+                Ok(i) => i,
+                Err(e) => {
+                    eprintln!("ERROR: Couldn't open image at {path:?} - {e}");
+                    return None;
+                }
+            };
+
+            Some((name, expected, actual(&image)))
+        })
+        .collect();
+
+    let mut screens = Confusion::default();
+    let mut fields: Vec<Confusion> = FIELDS.iter().map(|_| Confusion::default()).collect();
+    let mut mismatches = vec![];
+
+    for (name, expected, actual) in &results {
+        if expected != actual {
+            mismatches.push(name.clone());
+        }
+
+        screens.add(screen_name(expected), screen_name(actual));
+
+        for ((_, keys), confusion) in FIELDS.iter().zip(fields.iter_mut()) {
+            let expected = collect_fields(&expected["body"], keys);
+            let actual = collect_fields(&actual["body"], keys);
+
+            let mut paths: Vec<_> = expected.keys().chain(actual.keys()).collect();
+            paths.sort();
+            paths.dedup();
+
+            for path in paths {
+                confusion.add(
+                    expected.get(path).map_or(MISSING, |v| v),
+                    actual.get(path).map_or(MISSING, |v| v),
+                );
+            }
+        }
+    }
+
+    let mut metrics = BTreeMap::new();
+    metrics.insert("screen".to_string(), screens.into_metric());
+    for ((field, _), confusion) in FIELDS.iter().zip(fields) {
+        metrics.insert(field.to_string(), confusion.into_metric());
+    }
+
+    let report = Report {
+        frames: results.len(),
+        metrics,
+        mismatches,
+    };
+
+    print_report(&report);
+
+    if let Some(baseline) = &args.baseline {
+        match std::fs::read_to_string(baseline)
+            .map_err(|e| e.to_string())
+            .and_then(|b| serde_json::from_str::<Report>(&b).map_err(|e| e.to_string()))
+        {
+            Ok(baseline) => print_comparison(&baseline, &report),
+            Err(e) => eprintln!("ERROR: couldn't read the baseline at {baseline:?} - {e}"),
+        }
+    }
+
+    if let Some(path) = &args.report {
+        let json = serde_json::to_string_pretty(&report).unwrap();
+        std::fs::write(path, json).expect("couldn't write the report");
+        println!("\nwrote report to {path:?}");
+    }
+}
+
+/// The output for a frame, the same shape as the expectations
+fn actual(image: &image::DynamicImage) -> Value {
+    serde_json::from_str(&corpus::render(&analyze(image))).expect("rendered invalid JSON")
+}
+
+fn screen_name(event: &Value) -> &str {
+    event["name"].as_str().unwrap_or(MISSING)
+}
+
+/// Every value in `body` under one of `keys`, by where it was found
+fn collect_fields(body: &Value, keys: &[&str]) -> BTreeMap<String, String> {
+    let mut found = BTreeMap::new();
+    collect_into(body, keys, String::new(), &mut found);
+
+    found
+}
+
+fn collect_into(value: &Value, keys: &[&str], path: String, found: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = format!("{path}/{key}");
+                if keys.contains(&key.as_str()) {
+                    let label = match value {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    found.insert(path, label);
+                } else {
+                    collect_into(value, keys, path, found);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                collect_into(value, keys, format!("{path}/{i}"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn print_report(report: &Report) {
+    println!("evaluated {} frames", report.frames);
+
+    for (name, metric) in &report.metrics {
+        println!("\n== {name} (accuracy {:.1}%)\n", metric.accuracy * 100.0);
+        println!(
+            "{:<32} {:>9} {:>9} {:>8}",
+            "label", "precision", "recall", "support"
+        );
+        for (label, score) in &metric.labels {
+            println!(
+                "{:<32} {:>8.1}% {:>8.1}% {:>8}",
+                label,
+                score.precision * 100.0,
+                score.recall * 100.0,
+                score.support
+            );
+        }
+
+        print_confusion(&metric.confusion);
+    }
+
+    if !report.mismatches.is_empty() {
+        println!("\n{} frames differ:", report.mismatches.len());
+        for name in &report.mismatches {
+            println!("  {name}");
+        }
+    }
+}
+
+/// Prints the matrix, expected labels down the side and actual labels along
+/// the top. The labels get long, so they're numbered along the top.
+fn print_confusion(confusion: &Confusion) {
+    let labels = confusion.labels();
+
+    println!();
+    print!("{:<36}", "expected \\ actual");
+    for i in 0..labels.len() {
+        print!("{i:>5}");
+    }
+    println!();
+
+    for (i, expected) in labels.iter().enumerate() {
+        print!("{:>3} {:<32}", i, expected);
+        for actual in &labels {
+            match confusion.count(expected, actual) {
+                0 => print!("{:>5}", "."),
+                n => print!("{n:>5}"),
+            }
+        }
+        println!();
+    }
+}
+
+fn print_comparison(baseline: &Report, report: &Report) {
+    println!("\n== compared to the baseline\n");
+
+    for (name, metric) in &report.metrics {
+        let Some(before) = baseline.metrics.get(name) else {
+            println!("{name}: not in the baseline");
+            continue;
+        };
+
+        println!(
+            "{name}: {:.1}% -> {:.1}% ({:+.1})",
+            before.accuracy * 100.0,
+            metric.accuracy * 100.0,
+            (metric.accuracy - before.accuracy) * 100.0
+        );
+
+        for (label, score) in &metric.labels {
+            let Some(old) = before.labels.get(label) else {
+                continue;
+            };
+            // the report is rounded a little on its way through JSON
+            let changed = |a: f64, b: f64| (a - b).abs() > 1e-9;
+            if changed(old.precision, score.precision) || changed(old.recall, score.recall) {
+                println!(
+                    "  {label}: precision {:.1}% -> {:.1}%, recall {:.1}% -> {:.1}%",
+                    old.precision * 100.0,
+                    score.precision * 100.0,
+                    old.recall * 100.0,
+                    score.recall * 100.0
+                );
+            }
+        }
+    }
+
+    let fixed: Vec<_> = baseline
+        .mismatches
+        .iter()
+        .filter(|m| !report.mismatches.contains(m))
+        .collect();
+    let broken: Vec<_> = report
+        .mismatches
+        .iter()
+        .filter(|m| !baseline.mismatches.contains(m))
+        .collect();
+
+    println!("\nnow matching: {fixed:?}");
+    println!("no longer matching: {broken:?}");
+}
@@ -0,0 +1,224 @@
+use analyzer::analyze;
+use clap::Parser;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+mod corpus;
+
+type Reply = Response<std::io::Cursor<Vec<u8>>>;
+
+/// Serves a page on localhost for labelling the frames saved from a session
+/// (with `--store-frames`), to add them to the golden corpus. Each frame is
+/// filled in with what the analyzer makes of it now, to be confirmed or
+/// corrected. Labelled frames are copied into the inputs as the next
+/// `test_<n>.jpg`, with the label written alongside in the outputs. Only built
+/// with the `tools` feature.
+#[derive(Parser, Debug)]
+struct Args {
+    /// The saved session, e.g. `frames/<session>`
+    session: PathBuf,
+
+    /// Directory of input frames to add to
+    #[arg(long, default_value = corpus::INPUTS)]
+    inputs: PathBuf,
+
+    /// Directory of expected outputs to add to
+    #[arg(long, default_value = corpus::OUTPUTS)]
+    outputs: PathBuf,
+
+    /// The labelling page
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/label.html"))]
+    page: PathBuf,
+
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+}
+
+#[derive(Serialize)]
+struct Frame<'a> {
+    name: &'a str,
+    /// What it was saved as, if it's been labelled
+    saved: Option<&'a str>,
+}
+
+struct Labeller {
+    args: Args,
+    page: String,
+    frames: Vec<String>,
+    /// The corpus name each frame was saved as, so labelling it again
+    /// replaces it rather than adding it twice
+    saved: HashMap<String, String>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let page = std::fs::read_to_string(&args.page).expect("couldn't find the labelling page");
+    let frames = session_frames(&args.session);
+    if frames.is_empty() {
+        eprintln!("ERROR: there are no frames in {:?}", args.session);
+        std::process::exit(1);
+    }
+
+    let address = format!("127.0.0.1:{}", args.port);
+    let server = Server::http(&address).expect("couldn't start the server");
+    println!("labelling {} frames at http://{address}", frames.len());
+
+    let mut labeller = Labeller {
+        args,
+        page,
+        frames,
+        saved: HashMap::new(),
+    };
+
+    for request in server.incoming_requests() {
+        labeller.handle(request);
+    }
+}
+
+impl Labeller {
+    fn handle(&mut self, mut request: Request) {
+        let url = request.url().to_string();
+        let method = request.method().clone();
+        let (route, frame) = match url.trim_start_matches('/').split_once('/') {
+            Some((route, frame)) => (route, Some(frame)),
+            None => (url.trim_start_matches('/'), None),
+        };
+
+        // only ever the frames we listed, so nothing else can be read
+        let listed = frame.is_none_or(|f| self.frames.iter().any(|n| n == f));
+
+        let response = match (&method, route, frame) {
+            _ if !listed => error(404, "not one of the session's frames"),
+            (Method::Get, "", None) => html(&self.page),
+            (Method::Get, "frames", None) => json(&self.list()),
+            (Method::Get, "frames", Some(frame)) => self.image(frame),
+            (Method::Get, "guess", Some(frame)) => self.guess(frame),
+            (Method::Post, "label", Some(frame)) => {
+                let mut label = String::new();
+                match request.as_reader().read_to_string(&mut label) {
+                    Ok(_) => self.label(frame, &label),
+                    Err(e) => error(400, &format!("couldn't read the label - {e}")),
+                }
+            }
+            _ => error(404, "not found"),
+        };
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("ERROR: couldn't respond to {url} - {e}");
+        }
+    }
+
+    fn list(&self) -> Vec<Frame<'_>> {
+        self.frames
+            .iter()
+            .map(|name| Frame {
+                name,
+                saved: self.saved.get(name).map(|s| s.as_str()),
+            })
+            .collect()
+    }
+
+    fn image(&self, frame: &str) -> Reply {
+        match std::fs::read(self.args.session.join(frame)) {
+            Ok(bytes) => Response::from_data(bytes).with_header(content_type("image/jpeg")),
+            Err(e) => error(500, &format!("couldn't read {frame} - {e}")),
+        }
+    }
+
+    fn guess(&self, frame: &str) -> Reply {
+        match image::open(self.args.session.join(frame)) {
+            Ok(image) => Response::from_string(corpus::render(&analyze(&image)))
+                .with_header(content_type("application/json")),
+            Err(e) => error(500, &format!("couldn't open {frame} - {e}")),
+        }
+    }
+
+    /// Adds the frame to the corpus, expecting `label`
+    fn label(&mut self, frame: &str, label: &str) -> Reply {
+        // the expectations are compared as text, so the label is kept as it
+        // was written, as long as it's an event
+        let label = label.trim_end();
+        match serde_json::from_str::<Value>(label) {
+            Ok(event) if event["name"].is_string() && event.get("body").is_some() => {}
+            Ok(_) => return error(400, "a label needs a `name` and a `body`"),
+            Err(e) => return error(400, &format!("the label isn't valid JSON - {e}")),
+        }
+
+        let name = match self.saved.get(frame) {
+            Some(name) => name.clone(),
+            None => next_name(&self.args.inputs),
+        };
+        let input = self.args.inputs.join(format!("{name}.jpg"));
+        let output = corpus::expectation_path(&self.args.outputs, &input);
+
+        if let Err(e) = std::fs::copy(self.args.session.join(frame), &input)
+            .and_then(|_| std::fs::write(&output, label))
+        {
+            return error(500, &format!("couldn't save {frame} as {name} - {e}"));
+        }
+
+        println!("{frame} -> {input:?}");
+        self.saved.insert(frame.to_string(), name.clone());
+
+        json(&name)
+    }
+}
+
+/// The frames saved in a session, in the order they were captured
+fn session_frames(session: &Path) -> Vec<String> {
+    let paths = std::fs::read_dir(session).expect("couldn't open the session dir");
+    let mut names: Vec<String> = paths
+        .filter_map(|p| p.ok())
+        .map(|p| p.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".jpg"))
+        .collect();
+    names.sort_by_key(|name| (number(name), name.clone()));
+
+    names
+}
+
+/// The first `test_<n>` after every one in the corpus
+fn next_name(inputs: &Path) -> String {
+    let next = corpus::frames(inputs)
+        .iter()
+        .filter_map(|path| {
+            path.file_stem()?
+                .to_str()?
+                .strip_prefix("test_")?
+                .parse()
+                .ok()
+        })
+        .max()
+        .map_or(0, |n: u32| n + 1);
+
+    format!("test_{next}")
+}
+
+/// The number in a name like `frame_12.jpg`
+fn number(name: &str) -> Option<u64> {
+    let digits: String = name.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    digits.parse().ok()
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("a valid header")
+}
+
+fn html(page: &str) -> Reply {
+    Response::from_string(page).with_header(content_type("text/html; charset=utf-8"))
+}
+
+fn json<T: Serialize>(value: &T) -> Reply {
+    Response::from_string(serde_json::to_string(value).unwrap())
+        .with_header(content_type("application/json"))
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    eprintln!("ERROR: {message}");
+    Response::from_string(message).with_status_code(status)
+}
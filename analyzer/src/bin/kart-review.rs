@@ -0,0 +1,109 @@
+use analyzer::analyze;
+use clap::Parser;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+mod corpus;
+
+// regions are given against the frame `analyze` resizes everything to
+const FRAME_SIZE: [f32; 2] = [1280.0, 720.0];
+
+/// Builds a page to review changes to the analyzer's output against the
+/// expectations, before updating them with `UPDATE_EXPECT=1 cargo test`. Each
+/// changed frame is shown with the regions it was read from, next to the old
+/// and new output.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Directory of input frames
+    #[arg(long, default_value = corpus::INPUTS)]
+    inputs: PathBuf,
+
+    /// Directory of expected outputs
+    #[arg(long, default_value = corpus::OUTPUTS)]
+    outputs: PathBuf,
+
+    /// The page to fill in. `{/*content*/}` is replaced with the rows
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/template.html"))]
+    template: PathBuf,
+
+    /// Where to write the review page
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/review.html"))]
+    output: PathBuf,
+
+    /// Show every frame, not just the ones that changed
+    #[arg(long)]
+    all: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let frames = corpus::frames(&args.inputs);
+
+    let rows: Vec<_> = frames
+        .par_iter()
+        .filter_map(|path| {
+            let image = match image::open(path) {
+                Ok(i) => i,
+                Err(e) => {
+                    eprintln!("ERROR: Couldn't open image at {path:?} - {e}");
+                    return None;
+                }
+            };
+
+            let screen = analyze(&image);
+            let after = corpus::render(&screen);
+            let before =
+                std::fs::read_to_string(corpus::expectation_path(&args.outputs, path)).ok();
+
+            if !args.all && before.as_ref() == Some(&after) {
+                return None;
+            }
+
+            let regions = screen.map(|s| s.regions()).unwrap_or_default();
+
+            Some(row(path, &regions, before.as_deref(), &after))
+        })
+        .collect();
+
+    let template = std::fs::read_to_string(&args.template).expect("couldn't find template");
+    let review_page = template.replace("{/*content*/}", &rows.join("\n"));
+
+    std::fs::write(&args.output, review_page).expect("couldn't write the review page");
+
+    println!(
+        "{} of {} frames to review, written to {:?}",
+        rows.len(),
+        frames.len(),
+        args.output
+    );
+}
+
+fn row(path: &Path, regions: &[[u32; 4]], before: Option<&str>, after: &str) -> String {
+    let path = path.to_str().expect("not a valid path name");
+    let [frame_width, frame_height] = FRAME_SIZE;
+
+    let regions: String = regions
+        .iter()
+        .map(|[x, y, width, height]| {
+            format!(
+                "<div class=\"region\" style=\"left: {}%; top: {}%; width: {}%; height: {}%\"></div>",
+                *x as f32 / frame_width * 100.0,
+                *y as f32 / frame_height * 100.0,
+                *width as f32 / frame_width * 100.0,
+                *height as f32 / frame_height * 100.0,
+            )
+        })
+        .collect();
+
+    let before = before.map_or("(no expectation)".to_string(), escape);
+    let after = escape(after);
+
+    format!("<tr><td colspan=\"4\"><b>{path}</b></td></tr><tr><td><div class=\"frame\"><img src=\"{path}\" />{regions}</div></td><td><pre>{before}</pre></td><td><pre>{after}</pre></td><td><input type=\"checkbox\" /></td></tr>")
+}
+
+/// OCR'd names can have anything in them
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
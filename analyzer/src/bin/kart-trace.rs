@@ -0,0 +1,79 @@
+use analyzer::analyze_with_trace;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+/// Shows why frames came out the way they did. Every region that was hashed is
+/// drawn over the frame with how close it came to its nearest reference, along
+/// with the screens that were checked, and saved as `<name>_trace.png`.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Where to save the traced frames. By default, they're saved next to the
+    /// frames they're from
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Print the whole trace as JSON, rather than a summary
+    #[arg(long)]
+    json: bool,
+
+    /// Frames to trace
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    args.files.iter().for_each(|path| {
+        let image = match image::open(path) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("ERROR: Couldn't open image at {path:?} - {e}");
+                return;
+            }
+        };
+
+        let (screen, trace) = analyze_with_trace(&image);
+
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&trace).unwrap());
+        } else {
+            let checked: Vec<_> = trace.checks.iter().map(|c| c.screen).collect();
+            println!("{path:?}: {:?}", screen.as_ref().map(|s| s.event_type()));
+            println!("  checked {}", checked.join(", "));
+            for (region, decisions) in trace.regions() {
+                let closest = decisions[0];
+                println!(
+                    "  {region:?} {:<24} {:>3} <= {:<3} {}",
+                    closest
+                        .sample
+                        .label
+                        .as_ref()
+                        .unwrap_or(&closest.sample.reference),
+                    closest.sample.distance,
+                    closest.threshold.map_or("-".into(), |t| t.to_string()),
+                    if closest.matched() { "matched" } else { "" }
+                );
+            }
+        }
+
+        let Some(output) = output_location(path, args.output.as_deref()) else {
+            eprintln!("Couldn't work out where to save the trace for {path:?}");
+            return;
+        };
+        match trace.render(&image).save(&output) {
+            Ok(_) => println!("  saved to {output:?}"),
+            Err(e) => eprintln!("Failed to save the trace for {path:?} - {e}"),
+        }
+    });
+}
+
+fn output_location(path: &Path, output: Option<&Path>) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    let directory = match output {
+        Some(output) => output,
+        None => path.parent()?,
+    };
+
+    Some(directory.join(format!("{stem}_trace.png")))
+}
@@ -0,0 +1,202 @@
+use analyzer::{samples, thresholds, Frame, Sample, MANIFEST_PATH};
+use clap::Parser;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+mod corpus;
+
+const PLAYERS: [&str; 4] = ["player_one", "player_two", "player_three", "player_four"];
+
+/// Records how far each frame in the labelled corpus is from every reference,
+/// and works out the threshold for each reference that gets the most frames
+/// right. The expectations say what each frame should match.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Directory of input frames
+    #[arg(long, default_value = corpus::INPUTS)]
+    inputs: PathBuf,
+
+    /// Directory of expected outputs
+    #[arg(long, default_value = corpus::OUTPUTS)]
+    outputs: PathBuf,
+
+    /// Write the suggested thresholds into the reference manifest
+    #[arg(long)]
+    write: bool,
+
+    /// Where to write every distance that was recorded, as JSON
+    #[arg(long)]
+    distances: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Recorded {
+    frame: String,
+    #[serde(flatten)]
+    sample: Sample,
+    /// Whether the expectation says this should have matched
+    matches: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let recorded: Vec<Recorded> = corpus::frames(&args.inputs)
+        .par_iter()
+        .flat_map(|path| {
+            let frame = path.file_name().unwrap().to_string_lossy().to_string();
+            let expected: Option<Value> =
+                std::fs::read_to_string(corpus::expectation_path(&args.outputs, path))
+                    .ok()
+                    .and_then(|e| serde_json::from_str(&e).ok());
+            let (Some(expected), Ok(image)) = (expected, image::open(path)) else {
+                eprintln!("WARNING: skipping {path:?}, it has no expectation or can't be opened");
+                return vec![];
+            };
+
+            samples(&Frame::from(image))
+                .into_iter()
+                .filter_map(|sample| {
+                    let matches = should_match(&expected, &sample)?;
+
+                    Some(Recorded {
+                        frame: frame.clone(),
+                        sample,
+                        matches,
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut by_reference: BTreeMap<&str, Vec<(u32, bool)>> = BTreeMap::new();
+    for r in &recorded {
+        by_reference
+            .entry(&r.sample.reference)
+            .or_default()
+            .push((r.sample.distance, r.matches));
+    }
+
+    let mut manifest = thresholds().clone();
+
+    println!(
+        "{:<28} {:>6} {:>6} {:>8} {:>8} {:>10} {:>8}",
+        "reference", "match", "other", "current", "accuracy", "suggested", "accuracy"
+    );
+    for (reference, distances) in &by_reference {
+        let current = thresholds()[*reference];
+        let matching = distances.iter().filter(|(_, m)| *m).count();
+        let suggested = suggest(distances);
+
+        println!(
+            "{:<28} {:>6} {:>6} {:>8} {:>7.1}% {:>10} {:>8}",
+            reference,
+            matching,
+            distances.len() - matching,
+            current,
+            accuracy(distances, current) * 100.0,
+            suggested.map_or("-".into(), |t| t.to_string()),
+            suggested.map_or("-".into(), |t| format!(
+                "{:.1}%",
+                accuracy(distances, t) * 100.0
+            )),
+        );
+
+        if let Some(threshold) = suggested {
+            manifest.insert(reference.to_string(), threshold);
+        }
+    }
+
+    if let Some(path) = &args.distances {
+        let json = serde_json::to_string_pretty(&recorded).unwrap();
+        std::fs::write(path, json).expect("couldn't write the distances");
+        println!("\nwrote {} distances to {path:?}", recorded.len());
+    }
+
+    if args.write {
+        let json = serde_json::to_string_pretty(&manifest).unwrap();
+        std::fs::write(MANIFEST_PATH, json + "\n").expect("couldn't write the manifest");
+        println!("\nwrote the suggested thresholds to {MANIFEST_PATH}");
+    }
+}
+
+/// Whether the expectation for a frame says the sample should have matched
+/// its reference. `None` when the expectation doesn't say.
+fn should_match(expected: &Value, sample: &Sample) -> Option<bool> {
+    let screen = expected["name"].as_str()?;
+
+    match sample.reference.as_str() {
+        "intro" => return Some(screen == "intro_screen"),
+        "loading" => return Some(screen == "loading_screen"),
+        "main_menu" => return Some(screen == "main_menu_screen"),
+        "select_character" => return Some(screen == "select_character_screen"),
+        "match_result/speed" => {
+            let speed = expected["body"]["speed"].to_string();
+            return Some(screen == "match_result_screen" && sample.label.as_ref() == Some(&speed));
+        }
+        _ => {}
+    }
+
+    // everything else is only read off race frames
+    if screen != "race_screen" {
+        return None;
+    }
+
+    let player = &expected["body"][PLAYERS[sample.player? as usize]];
+    if player.is_null() {
+        return (sample.reference == "race/lap_flag").then_some(false);
+    }
+
+    let label = |field: &str| match &player[field] {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    };
+
+    match sample.reference.as_str() {
+        "race/lap_flag" => Some(true),
+        "race/finish" => Some(label("status").as_deref() == Some("finish")),
+        "race/blue_shell_warning" => Some(label("blue_shell_warning").as_deref() == Some("true")),
+        "race/position" => Some(label("position") == sample.label),
+        reference if reference.starts_with("items/") => Some(label("item") == sample.label),
+        _ => None,
+    }
+}
+
+/// How many of the distances are on the right side of `threshold`
+fn accuracy(distances: &[(u32, bool)], threshold: u32) -> f64 {
+    let correct = distances
+        .iter()
+        .filter(|(distance, matches)| (*distance <= threshold) == *matches)
+        .count();
+
+    correct as f64 / distances.len() as f64
+}
+
+/// The threshold that gets the most distances right. Where a range of them
+/// do equally well, the middle of it leaves the most room either side. Only
+/// suggested when there are both matches and non-matches to go by.
+fn suggest(distances: &[(u32, bool)]) -> Option<u32> {
+    let has_matches = distances.iter().any(|(_, m)| *m);
+    let has_others = distances.iter().any(|(_, m)| !*m);
+    if !has_matches || !has_others {
+        return None;
+    }
+
+    let highest = distances
+        .iter()
+        .map(|(d, _)| *d)
+        .filter(|d| *d != u32::MAX)
+        .max()?;
+
+    let scores: Vec<f64> = (0..=highest).map(|t| accuracy(distances, t)).collect();
+    let best = scores.iter().cloned().fold(0.0, f64::max);
+
+    let low = scores.iter().position(|s| *s == best)?;
+    let high = low + scores[low..].iter().take_while(|s| **s == best).count() - 1;
+
+    Some(((low + high) / 2) as u32)
+}
@@ -0,0 +1,138 @@
+use image::{imageops::FilterType, DynamicImage, RgbImage};
+use lazy_static::lazy_static;
+
+// Every row of the results screens starts with the racer's character icon.
+// The icons are drawn the same size on both screens, give or take, so they're
+// scaled down and compared pixel by pixel with icons cut from real results
+// screens. Only the 12 characters in the corpus have a reference, and any
+// other character is left unnamed rather than given the nearest of those.
+
+// icons are compared at this size, which also smooths over capture noise
+const ICON_SIZE: u32 = 24;
+// the corners of the icon show the row behind it, which is a different colour
+// for each player, so only the middle is compared
+const ICON_INSET: u32 = 4;
+// how far apart (on average, per channel) an icon can be from a reference and
+// still be that character. The right character is within 25 on every row of
+// the corpus, and the next closest is always more than 40 away
+const MAX_DIFFERENCE: u32 = 32;
+
+struct CharacterReference {
+    name: &'static str,
+    icon: RgbImage,
+}
+
+macro_rules! load_character {
+    ($name:expr, $file:expr $(,)?) => {{
+        let f = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/references/characters/",
+            $file
+        ));
+        CharacterReference {
+            name: $name,
+            icon: scale(&image::load_from_memory(f).unwrap()),
+        }
+    }};
+}
+
+lazy_static! {
+    static ref CHARACTERS: [CharacterReference; 12] = [
+        load_character!("Baby Luigi", "baby-luigi.png"),
+        load_character!("Baby Peach", "baby-peach.png"),
+        load_character!("Baby Rosalina", "baby-rosalina.png"),
+        load_character!("Birdo (Light Blue)", "birdo-light-blue.png"),
+        load_character!("Donkey Kong", "donkey-kong.png"),
+        load_character!("Inkling Girl", "inkling-girl.png"),
+        load_character!("Koopa Troopa", "koopa-troopa.png"),
+        load_character!("Luigi", "luigi.png"),
+        load_character!("Pink Gold Peach", "pink-gold-peach.png"),
+        load_character!("Roy", "roy.png"),
+        load_character!("Wario", "wario.png"),
+        load_character!("Wendy", "wendy.png"),
+    ];
+}
+
+pub(crate) fn load_references() {
+    lazy_static::initialize(&CHARACTERS);
+}
+
+fn scale(icon: &DynamicImage) -> RgbImage {
+    icon.resize_exact(ICON_SIZE, ICON_SIZE, FilterType::Triangle)
+        .to_rgb8()
+}
+
+/// How far apart two scaled icons are, on average per channel, leaving out
+/// their edges
+fn difference(a: &RgbImage, b: &RgbImage) -> u32 {
+    let inside = ICON_INSET..ICON_SIZE - ICON_INSET;
+    let mut total = 0;
+    let mut count = 0;
+
+    for y in inside.clone() {
+        for x in inside.clone() {
+            let a = a.get_pixel(x, y).0;
+            let b = b.get_pixel(x, y).0;
+            for channel in 0..3 {
+                total += a[channel].abs_diff(b[channel]) as u32;
+                count += 1;
+            }
+        }
+    }
+
+    total / count
+}
+
+/// The character whose icon this is, if it's one of the characters there's a
+/// reference for. Any other character gives `None`, however close the nearest
+/// reference is.
+pub(crate) fn find_character(icon: &DynamicImage) -> Option<&'static str> {
+    let icon = scale(icon);
+
+    CHARACTERS
+        .iter()
+        .map(|character| (difference(&icon, &character.icon), character.name))
+        .filter(|(difference, _)| *difference <= MAX_DIFFERENCE)
+        .min_by_key(|(difference, _)| *difference)
+        .map(|(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_character, CHARACTERS};
+    use image::{DynamicImage, RgbImage};
+
+    // swaps the red and green of every pixel, which turns Luigi's green cap
+    // into Mario's red one
+    fn recolor(icon: &RgbImage) -> DynamicImage {
+        let mut icon = icon.clone();
+        for pixel in icon.pixels_mut() {
+            let [r, g, b] = pixel.0;
+            pixel.0 = [g, r, b];
+        }
+        DynamicImage::ImageRgb8(icon)
+    }
+
+    fn reference(name: &str) -> &'static RgbImage {
+        &CHARACTERS
+            .iter()
+            .find(|character| character.name == name)
+            .unwrap()
+            .icon
+    }
+
+    #[test]
+    fn finds_every_reference() {
+        for character in CHARACTERS.iter() {
+            let icon = DynamicImage::ImageRgb8(character.icon.clone());
+            assert_eq!(find_character(&icon), Some(character.name));
+        }
+    }
+
+    #[test]
+    fn unknown_characters_are_not_named() {
+        assert_eq!(find_character(&recolor(reference("Luigi"))), None);
+        assert_eq!(find_character(&recolor(reference("Baby Luigi"))), None);
+        assert_eq!(find_character(&DynamicImage::new_rgb8(24, 24)), None);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::frame::{Frame, HEIGHT, WIDTH};
+use crate::screens::Screen;
+use crate::{detect, SCREENS};
+use std::collections::{BTreeMap, VecDeque};
+
+// Most frames look a lot like the one before, and a lot like other frames of
+// the same screen. A thumbnail of the frame is cheap to make, so that's
+// checked first: if it's hardly moved since the last frame, the last answer
+// still stands, and if it's close to a screen we've seen before, that
+// screen's detector goes first. Trying the detectors out of order only comes
+// to the same answer for screens nothing else is drawn over - a race can have
+// the pause or highlights menu over it, or be a replay, and those are only
+// told apart by `SCREENS` checking them first. Those screens always go
+// through in order.
+
+// each cell of the thumbnail is the average of this many pixels square
+const CELL: u32 = 40;
+const COLUMNS: u32 = WIDTH / CELL;
+const ROWS: u32 = HEIGHT / CELL;
+// only every other pixel in a cell is looked at, which still catches strokes
+// as thin as the lap counter's
+const STRIDE: usize = 2;
+// capture noise moves a cell's average by a point or two. Anything that
+// changes what a detector would read moves it by more than this
+const UNCHANGED: u8 = 3;
+// how close (on average, per channel) a thumbnail has to be to one we've
+// seen for that screen to be tried first
+const SIMILAR: u32 = 24;
+// how many screens are tried before going through the rest in order
+const CANDIDATES: usize = 3;
+// how many thumbnails are kept for each screen
+const REMEMBERED: usize = 4;
+// screens that another one, earlier in `SCREENS`, can match on top of
+const OVERLAID: [&str; 1] = ["race"];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Thumbnail(Vec<[u8; 3]>);
+
+impl Thumbnail {
+    fn new(frame: &Frame) -> Thumbnail {
+        let rgb = frame.rgb();
+        let samples = ((CELL as usize).div_ceil(STRIDE).pow(2)) as u32;
+
+        let cells = (0..ROWS)
+            .flat_map(|row| (0..COLUMNS).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let mut total = [0u32; 3];
+                for y in (row * CELL..(row + 1) * CELL).step_by(STRIDE) {
+                    for x in (column * CELL..(column + 1) * CELL).step_by(STRIDE) {
+                        let pixel = rgb.get_pixel(x, y).0;
+                        for (total, channel) in total.iter_mut().zip(pixel) {
+                            *total += channel as u32;
+                        }
+                    }
+                }
+
+                total.map(|channel| (channel / samples) as u8)
+            })
+            .collect();
+
+        Thumbnail(cells)
+    }
+
+    /// The most any one cell has changed by
+    fn change(&self, other: &Thumbnail) -> u8 {
+        self.channels(other).max().unwrap_or(0)
+    }
+
+    /// How far apart the two are, on average
+    fn distance(&self, other: &Thumbnail) -> u32 {
+        let total: u32 = self.channels(other).map(|d| d as u32).sum();
+
+        total / (self.0.len() as u32 * 3)
+    }
+
+    fn channels<'a>(&'a self, other: &'a Thumbnail) -> impl Iterator<Item = u8> + 'a {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .flat_map(|(a, b)| (0..3).map(|c| a[c].abs_diff(b[c])))
+    }
+}
+
+/// Analyzes a stream of frames, doing as little as it can for each one. It
+/// gives the same answers as `analyze`, unless a frame changes too little for
+/// the thumbnail to notice.
+#[derive(Debug, Default)]
+pub struct Classifier {
+    last: Option<(Thumbnail, Option<Screen>)>,
+    seen: BTreeMap<&'static str, VecDeque<Thumbnail>>,
+}
+
+impl Classifier {
+    pub fn new() -> Self {
+        Classifier::default()
+    }
+
+    pub fn analyze(&mut self, frame: &Frame) -> Option<Screen> {
+        let thumbnail = Thumbnail::new(frame);
+
+        if let Some((last, screen)) = &self.last {
+            if last.change(&thumbnail) <= UNCHANGED {
+                return screen.clone();
+            }
+        }
+
+        let screen = self.classify(frame, &thumbnail);
+        self.last = Some((thumbnail, screen.clone()));
+
+        screen
+    }
+
+    fn classify(&mut self, frame: &Frame, thumbnail: &Thumbnail) -> Option<Screen> {
+        for candidate in self.candidates(thumbnail) {
+            let Some((_, compare, process, _)) = SCREENS.iter().find(|(n, ..)| *n == candidate)
+            else {
+                continue;
+            };
+
+            if compare(frame) {
+                return process(frame);
+            }
+        }
+
+        let mut matched = None;
+        let screen = detect(frame, |name, m| {
+            if m {
+                matched = Some(name);
+            }
+        });
+
+        if let Some(name) = matched {
+            let seen = self.seen.entry(name).or_default();
+            if seen.len() == REMEMBERED {
+                seen.pop_front();
+            }
+            seen.push_back(thumbnail.clone());
+        }
+
+        screen
+    }
+
+    /// The screens we've seen a thumbnail like this one for, closest first
+    fn candidates(&self, thumbnail: &Thumbnail) -> Vec<&'static str> {
+        let mut candidates: Vec<_> = self
+            .seen
+            .iter()
+            .filter_map(|(name, seen)| {
+                if OVERLAID.contains(name) {
+                    return None;
+                }
+                let closest = seen.iter().map(|s| s.distance(thumbnail)).min()?;
+
+                (closest <= SIMILAR).then_some((closest, *name))
+            })
+            .collect();
+        candidates.sort();
+
+        candidates
+            .into_iter()
+            .take(CANDIDATES)
+            .map(|(_, name)| name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Classifier, Thumbnail, OVERLAID, UNCHANGED};
+    use crate::frame::Frame;
+    use crate::{analyze_frame, SCREENS};
+    use image::{DynamicImage, Rgb};
+
+    fn frame(name: &str) -> DynamicImage {
+        let path = format!("{}/tests/inputs/{name}.jpg", env!("CARGO_MANIFEST_DIR"));
+
+        image::open(path).unwrap()
+    }
+
+    #[test]
+    fn small_changes_are_noticed() {
+        let image = frame("test_1");
+
+        // about the size of a digit on the race clock
+        let mut changed = image.to_rgb8();
+        for y in 60..84 {
+            for x in 1700..1716 {
+                let Rgb([r, g, b]) = *changed.get_pixel(x, y);
+                changed.put_pixel(x, y, Rgb([255 - r, 255 - g, 255 - b]));
+            }
+        }
+
+        let before = Thumbnail::new(&Frame::new(&image));
+        let after = Thumbnail::new(&Frame::new(&DynamicImage::ImageRgb8(changed)));
+        assert!(before.change(&after) > UNCHANGED);
+    }
+
+    #[test]
+    fn overlaid_screens_are_listed() {
+        let input_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs");
+        let paths = std::fs::read_dir(input_path)
+            .unwrap()
+            .filter_map(|p| p.ok());
+
+        for path in paths.map(|p| p.path()) {
+            let frame = Frame::new(&image::open(&path).unwrap());
+            let matched: Vec<_> = SCREENS
+                .iter()
+                .filter(|(_, compare, ..)| compare(&frame))
+                .map(|(name, ..)| *name)
+                .collect();
+
+            // everything after the first match is something `SCREENS` only
+            // gets right because of the order it's in
+            for name in matched.iter().skip(1) {
+                assert!(OVERLAID.contains(name), "{path:?} also matches {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn screens_over_a_race_are_not_taken_for_it() {
+        // the highlights menu with the race behind it, and a replay
+        for name in ["test_79", "test_181"] {
+            let frame = Frame::new(&frame(name));
+            let expected = analyze_frame(&frame);
+
+            // as if a frame that looks just like it had been a race
+            let mut classifier = Classifier::new();
+            classifier
+                .seen
+                .entry("race")
+                .or_default()
+                .push_back(Thumbnail::new(&frame));
+
+            assert_eq!(classifier.analyze(&frame), expected, "{name}");
+        }
+    }
+}
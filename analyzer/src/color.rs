@@ -1,5 +1,6 @@
 use image::GenericImage;
 use image::GenericImageView;
+use image::Pixel;
 use image::Rgb;
 
 pub const COLOR_THRESHOLD: u16 = 45_000;
@@ -23,15 +24,27 @@ fn min([red, green, blue]: [u16; 3]) -> u16 {
     return red.min(green).min(blue);
 }
 
-pub fn average_colors(im: &image::DynamicImage) -> [u16; 3] {
-    let size = (im.width() * im.height()) as usize;
-    im.to_rgb16()
-        .pixels()
-        .map(|p| [p.0[0] as usize, p.0[1] as usize, p.0[2] as usize])
-        .reduce(|a, p| [a[0] + p[0], a[1] + p[1], a[2] + p[2]])
-        .map(|p| [p[0] / size, p[1] / size, p[2] / size])
-        .map(|p| [p[0] as u16, p[1] as u16, p[2] as u16])
-        .expect("Couldn't find the totals")
+/// The average colour of an image, or a view of part of one, at the same
+/// depth as `to_rgb16` would give
+pub fn average_colors<I>(im: &I) -> [u16; 3]
+where
+    I: GenericImageView,
+    I::Pixel: Pixel<Subpixel = u8>,
+{
+    let (width, height) = im.dimensions();
+    let mut total = [0usize; 3];
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = im.get_pixel(x, y).to_rgb();
+            for (total, channel) in total.iter_mut().zip(pixel.0) {
+                *total += channel as usize;
+            }
+        }
+    }
+
+    // `to_rgb16` scales each channel by 257
+    let size = (width * height) as usize;
+    total.map(|channel| (channel * 257 / size) as u16)
 }
 
 pub fn mostly_red([r, g, b]: [u16; 3]) -> bool {
@@ -46,6 +59,26 @@ pub fn mostly_blue([r, g, b]: [u16; 3]) -> bool {
     return b > 40_000 && r < 20_000 && g < 20_000;
 }
 
+pub fn mostly_yellow([r, g, b]: [u16; 3]) -> bool {
+    return r > 50_000 && g > 40_000 && b < 20_000;
+}
+
+/// Each player has their own colour for highlights and cursors - yellow, blue,
+/// red and green, in that order.
+pub fn player_color([r, g, b]: [u16; 3]) -> Option<u8> {
+    if r > COLOR_THRESHOLD && g > COLOR_THRESHOLD {
+        Some(0)
+    } else if g > COLOR_THRESHOLD && b > COLOR_THRESHOLD {
+        Some(1)
+    } else if g > COLOR_THRESHOLD {
+        Some(3)
+    } else if r > COLOR_THRESHOLD {
+        Some(2)
+    } else {
+        None
+    }
+}
+
 pub fn max_color_diff(image: &mut image::DynamicImage, cutoff: u16) {
     for y in 0..image.height() {
         for x in 0..image.width() {
@@ -62,8 +95,46 @@ pub fn max_color_diff(image: &mut image::DynamicImage, cutoff: u16) {
     }
 }
 
-pub fn get_overall_average(image: &image::DynamicImage) -> u32 {
-    let [r, g, b] = average_colors(&image);
+/// A cutoff for `max_color_diff` that picks light text out from whatever's
+/// behind it, for rows that are see-through. Text only covers a little of a
+/// row, so the middle lightness is the background and the lightest pixels are
+/// the text.
+pub fn light_text_cutoff(image: &image::DynamicImage) -> u16 {
+    let mut lightness: Vec<u8> = image.to_luma8().pixels().map(|p| p.0[0]).collect();
+    if lightness.is_empty() {
+        return u8::MAX as u16;
+    }
+    lightness.sort_unstable();
+
+    let background = lightness[lightness.len() / 2] as u16;
+    let text = lightness[lightness.len() * 19 / 20] as u16;
+
+    (background + text) / 2
+}
+
+/// Picks dark text out from a coloured background: keeps pixels whose highest
+/// channel is below `cutoff`, and draws them in white. A bright colour behind
+/// the text can be as dark as it on average, but never in every channel.
+pub fn max_darkness(image: &mut image::DynamicImage, cutoff: u8) {
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+
+            if r.max(g).max(b) < cutoff {
+                image.put_pixel(x, y, image::Rgba::from([255, 255, 255, 1]));
+            } else {
+                image.put_pixel(x, y, image::Rgba::from([0, 0, 0, 1]));
+            }
+        }
+    }
+}
+
+pub fn get_overall_average<I>(image: &I) -> u32
+where
+    I: GenericImageView,
+    I::Pixel: Pixel<Subpixel = u8>,
+{
+    let [r, g, b] = average_colors(image);
 
     let total = r as u32 + g as u32 + b as u32;
 
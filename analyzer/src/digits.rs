@@ -0,0 +1,402 @@
+use crate::color::average_colors;
+use image::DynamicImage;
+
+// Every number in the game (scores, points, timers, coins, laps) is drawn in
+// a font that's close enough to a seven segment display that a digit can be
+// told apart by checking which segments have ink in them. Each digit is
+// sampled against a template of where the segments sit, laid out for the
+// scoreboard digits, and scaled to fit whatever size is being read.
+
+// the size of the digit the sample points were laid out on
+const TEMPLATE_WIDTH: u32 = 19;
+const TEMPLATE_HEIGHT: u32 = 38;
+
+// where the digit itself sits inside that space
+const GLYPH_LEFT: u32 = 2;
+const GLYPH_TOP: u32 = 6;
+const GLYPH_WIDTH: u32 = 16;
+const GLYPH_HEIGHT: u32 = 25;
+const MIN_GLYPH_WIDTH: u32 = 3;
+const MIN_GLYPH_HEIGHT: u32 = 15;
+
+// the segments, as `[x, y, width, height]` on the template - top, top left,
+// top right, center, bottom left, bottom right and bottom
+const SEGMENTS: [[u32; 4]; 7] = [
+    [7, 6, 3, 1],
+    [2, 10, 1, 3],
+    [16, 10, 1, 3],
+    [7, 18, 3, 1],
+    [2, 22, 3, 1],
+    [16, 22, 3, 1],
+    [7, 30, 3, 1],
+];
+
+// which segments are lit for each digit, in the same order as `SEGMENTS`
+const DIGITS: [[bool; 7]; 10] = [
+    [true, true, true, false, true, true, true],
+    [false, false, true, false, false, true, false],
+    [true, false, true, true, true, false, true],
+    [true, false, true, true, false, true, true],
+    [false, true, true, true, false, true, false],
+    [true, true, false, true, false, true, true],
+    [true, true, false, true, true, true, true],
+    [true, false, true, false, false, true, false],
+    [true, true, true, true, true, true, true],
+    [true, true, true, true, false, true, true],
+];
+
+// a segment darker than this on average has ink in it
+const INK_CUTOFF: u16 = 25_000;
+
+/// A value read off the screen, along with how sure we are of it from 0 to 1.
+/// A number is only as certain as its least clear digit, so a single smudged
+/// digit is enough to bring it down.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct Reading<T> {
+    pub value: T,
+    pub confidence: f32,
+}
+
+/// Reads a single digit out of a section that has already been through
+/// `max_color_diff`, where the digit is drawn in black. The digit is expected
+/// to fill a `width` x `height` box starting `x_offset` from the left, with
+/// the same margins as a scoreboard digit.
+pub(crate) fn read_digit(
+    section: &DynamicImage,
+    x_offset: u32,
+    width: u32,
+    height: u32,
+) -> Option<Reading<u8>> {
+    // strokes aren't always exactly where the template has them, so each
+    // segment is also sampled a pixel either side, and the darkest is kept
+    let samples = SEGMENTS.map(|[x, y, w, h]| {
+        let [x, y] = [
+            x_offset + (x * width / TEMPLATE_WIDTH),
+            y * height / TEMPLATE_HEIGHT,
+        ];
+        let [w, h] = [
+            (w * width / TEMPLATE_WIDTH).max(1),
+            (h * height / TEMPLATE_HEIGHT).max(1),
+        ];
+        let nudges = if w >= h {
+            [[x, y.saturating_sub(1)], [x, y], [x, y + 1]]
+        } else {
+            [[x.saturating_sub(1), y], [x, y], [x + 1, y]]
+        };
+
+        nudges
+            .iter()
+            .filter(|[x, y]| x + w <= section.width() && y + h <= section.height())
+            .map(|[x, y]| {
+                let [r, g, b] = average_colors(&section.crop_imm(*x, *y, w, h));
+
+                ((r as u32 + g as u32 + b as u32) / 3) as u16
+            })
+            .min()
+            .unwrap_or(u16::MAX)
+    });
+    let lit = samples.map(|sample| sample < INK_CUTOFF);
+
+    let value = DIGITS.iter().position(|digit| *digit == lit)?;
+
+    // how far each segment was from going the other way, on average
+    let confidence = samples
+        .iter()
+        .map(|sample| {
+            if *sample < INK_CUTOFF {
+                (INK_CUTOFF - sample) as f32 / INK_CUTOFF as f32
+            } else {
+                (sample - INK_CUTOFF) as f32 / (u16::MAX - INK_CUTOFF) as f32
+            }
+        })
+        .sum::<f32>()
+        / SEGMENTS.len() as f32;
+
+    Some(Reading {
+        value: value as u8,
+        confidence,
+    })
+}
+
+/// Reads a number whose digits are always in the same place, like the
+/// scoreboard scores, with each digit `x_offsets` from the left of `section`
+pub(crate) fn read_fixed_number(
+    section: &DynamicImage,
+    x_offsets: &[u32],
+    width: u32,
+    height: u32,
+) -> Option<Reading<u32>> {
+    x_offsets.iter().try_fold(
+        Reading {
+            value: 0,
+            confidence: 1.0,
+        },
+        |number, x_offset| {
+            read_cell(section, *x_offset, width, height).map(|digit| append(number, digit))
+        },
+    )
+}
+
+/// Reads the digit in one of a fixed number's places. A 1 is drawn in the
+/// middle of its place, rather than down the right hand side where the
+/// template expects it, so it's picked out on its own first.
+pub(crate) fn read_cell(
+    section: &DynamicImage,
+    x_offset: u32,
+    width: u32,
+    height: u32,
+) -> Option<Reading<u8>> {
+    // only the rows the digit can be in, so the edges of the rows above and
+    // below can't join up with it
+    let top = (GLYPH_TOP - 2) * height / TEMPLATE_HEIGHT;
+    let bottom = ((GLYPH_TOP + GLYPH_HEIGHT + 2) * height / TEMPLATE_HEIGHT).min(section.height());
+    let cell = section.crop_imm(x_offset, top, width, bottom - top);
+
+    if let Some((_, glyphs)) = find_glyphs(&cell) {
+        if let [glyph @ [_, _, glyph_width, glyph_height]] = glyphs[..] {
+            if glyph_width * 3 < glyph_height {
+                return read_glyph(&cell, glyph);
+            }
+        }
+    }
+
+    read_digit(section, x_offset, width, height)
+}
+
+/// Reads a whole number out of a section that has already been through
+/// `max_color_diff`, where the digits are drawn in black. Digits are found by
+/// the gaps between them, so the number doesn't need to line up with anything.
+/// Marks much shorter than the digits (like a `+` in front) are skipped.
+pub(crate) fn read_number(section: &DynamicImage) -> Option<Reading<u32>> {
+    let (_, digits) = find_glyphs(section)?;
+
+    read_glyphs(section, &digits)
+}
+
+/// Like `read_number`, but a `-` in front makes the number negative
+pub(crate) fn read_signed_number(section: &DynamicImage) -> Option<Reading<i32>> {
+    let (marks, digits) = find_glyphs(section)?;
+    let number = read_glyphs(section, &digits)?;
+
+    // a `+` is as tall as it is wide, where a `-` is only a short bar
+    let first_digit = digits[0][0];
+    let negative = marks
+        .iter()
+        .rev()
+        .find(|[x, _, _, _]| *x < first_digit)
+        .is_some_and(|[_, _, width, height]| height * 2 < *width);
+    let value = i32::try_from(number.value).ok()?;
+
+    Some(Reading {
+        value: if negative { -value } else { value },
+        confidence: number.confidence,
+    })
+}
+
+/// A glyph's bounds in a section, as `[x, y, width, height]`
+type Glyph = [u32; 4];
+
+/// Finds the glyphs in a section, split into marks much shorter than the
+/// digits and the digits themselves
+fn find_glyphs(section: &DynamicImage) -> Option<(Vec<Glyph>, Vec<Glyph>)> {
+    let luma = section.to_luma8();
+    let ink = |x: u32, y: u32| luma.get_pixel(x, y).0[0] < 128;
+
+    let mut glyphs: Vec<Glyph> = vec![];
+    let mut left = None;
+    for x in 0..=luma.width() {
+        let has_ink = x < luma.width() && (0..luma.height()).any(|y| ink(x, y));
+
+        match (has_ink, left) {
+            (true, None) => left = Some(x),
+            (false, Some(start)) => {
+                let rows: Vec<u32> = (0..luma.height())
+                    .filter(|y| (start..x).any(|x| ink(x, *y)))
+                    .collect();
+                let top = rows[0];
+                let bottom = rows[rows.len() - 1];
+
+                // anything thinner than a 1 is noise, or the edge of
+                // something else creeping into the section. A digit never
+                // runs from the very top of the section to the very bottom
+                // either, where the edge of a row sliding past does
+                let edge = top == 0 && bottom == luma.height() - 1;
+                if x - start >= MIN_GLYPH_WIDTH && !edge {
+                    glyphs.push([start, top, x - start, bottom - top + 1]);
+                }
+                left = None;
+            }
+            _ => {}
+        }
+    }
+
+    let tallest = glyphs.iter().map(|[_, _, _, height]| *height).max()?;
+    if tallest < MIN_GLYPH_HEIGHT {
+        return None;
+    }
+    let (digits, marks): (Vec<_>, Vec<_>) = glyphs
+        .into_iter()
+        .partition(|[_, _, _, height]| height * 10 >= tallest * 8);
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    Some((marks, digits))
+}
+
+fn read_glyphs(section: &DynamicImage, digits: &[Glyph]) -> Option<Reading<u32>> {
+    digits.iter().try_fold(
+        Reading {
+            value: 0,
+            confidence: 1.0,
+        },
+        |number, glyph| read_glyph(section, *glyph).map(|digit| append(number, digit)),
+    )
+}
+
+fn append(number: Reading<u32>, digit: Reading<u8>) -> Reading<u32> {
+    Reading {
+        value: number.value * 10 + u32::from(digit.value),
+        confidence: number.confidence.min(digit.confidence),
+    }
+}
+
+/// Reads a single digit that's been cut out of a number by `read_number`
+fn read_glyph(section: &DynamicImage, [x, y, width, height]: Glyph) -> Option<Reading<u8>> {
+    // a 1 is a single stroke, so there's nothing to sample either side of it.
+    // how sure we are comes down to how solid that stroke is
+    if width * 3 < height {
+        let stroke = section.crop_imm(x, y, width, height).to_luma8();
+        let ink = stroke.pixels().filter(|p| p.0[0] < 128).count();
+
+        return Some(Reading {
+            value: 1,
+            confidence: ink as f32 / (width * height) as f32,
+        });
+    }
+
+    // `read_digit` samples a digit with some space around it, so put that
+    // space back around the glyph before handing it over
+    let box_width = width * TEMPLATE_WIDTH / GLYPH_WIDTH;
+    let box_height = height * TEMPLATE_HEIGHT / GLYPH_HEIGHT;
+    let mut padded = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+        box_width,
+        box_height,
+        image::Rgba([255, 255, 255, 255]),
+    ));
+    image::imageops::overlay(
+        &mut padded,
+        &section.crop_imm(x, y, width, height),
+        (GLYPH_LEFT * width / GLYPH_WIDTH) as i64,
+        (GLYPH_TOP * height / GLYPH_HEIGHT) as i64,
+    );
+
+    read_digit(&padded, 0, box_width, box_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_fixed_number, read_number, read_signed_number, Reading};
+    use crate::color::{light_text_cutoff, max_color_diff};
+    use image::{DynamicImage, GenericImage};
+
+    // the confidence any of the numbers in the corpus should be read with
+    const MIN_CONFIDENCE: f32 = 0.5;
+
+    fn anywhere(section: &DynamicImage) -> Option<Reading<u32>> {
+        read_number(section)
+    }
+
+    fn scoreboard(section: &DynamicImage) -> Option<Reading<u32>> {
+        read_fixed_number(section, &[0, 23], 19, 38)
+    }
+
+    fn dark(section: &mut DynamicImage) {
+        max_color_diff(section, 130);
+    }
+
+    // CPU rows fade out towards the bottom of the match results, leaving their
+    // scores lighter than the background
+    fn faded(section: &mut DynamicImage) {
+        max_color_diff(section, 130);
+        section.invert();
+    }
+
+    fn light(section: &mut DynamicImage) {
+        let cutoff = light_text_cutoff(section);
+        max_color_diff(section, cutoff);
+        section.invert();
+    }
+
+    macro_rules! test_number {
+        ($name:ident, $prepare:ident, $read:ident, $value:expr) => {
+            #[test]
+            fn $name() {
+                let image_data = include_bytes!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/spec-data/digits/",
+                    stringify!($name),
+                    ".png"
+                ));
+                let mut section =
+                    image::load_from_memory(image_data).expect("failed to open image");
+                $prepare(&mut section);
+
+                let reading = $read(&section).expect("couldn't read a number");
+
+                assert_eq!(reading.value, $value);
+                assert!(
+                    reading.confidence >= MIN_CONFIDENCE,
+                    "read {} with a confidence of {}",
+                    reading.value,
+                    reading.confidence
+                );
+            }
+        };
+    }
+
+    test_number!(match_result_score_77, dark, scoreboard, 77);
+    test_number!(match_result_score_76, dark, scoreboard, 76);
+    test_number!(match_result_score_55, dark, scoreboard, 55);
+    test_number!(match_result_score_44, faded, scoreboard, 44);
+    test_number!(match_result_score_43, dark, scoreboard, 43);
+    test_number!(match_result_score_35, faded, scoreboard, 35);
+    test_number!(match_result_score_23, faded, scoreboard, 23);
+    test_number!(match_result_score_22, faded, scoreboard, 22);
+
+    test_number!(race_result_points_12, dark, anywhere, 12);
+    test_number!(race_result_points_10, light, anywhere, 10);
+    test_number!(race_result_points_9, dark, anywhere, 9);
+    test_number!(race_result_points_8, light, anywhere, 8);
+    test_number!(race_result_points_6, dark, anywhere, 6);
+
+    test_number!(race_result_total_27, light, anywhere, 27);
+    test_number!(race_result_total_40, dark, anywhere, 40);
+    test_number!(race_result_total_17, light, anywhere, 17);
+    test_number!(race_result_total_19, dark, anywhere, 19);
+    test_number!(race_result_total_21, light, anywhere, 21);
+    test_number!(race_result_total_6, light, anywhere, 6);
+
+    #[test]
+    fn reads_negative_numbers() {
+        let image_data = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec-data/digits/race_result_points_12.png"
+        ));
+        let mut section = image::load_from_memory(image_data).expect("failed to open image");
+        dark(&mut section);
+
+        assert_eq!(read_signed_number(&section).map(|n| n.value), Some(12));
+
+        // turn the + in front into a -
+        for x in 11..28 {
+            for y in 0..section.height() {
+                let color = if (17..=19).contains(&y) { 0 } else { 255 };
+                section.put_pixel(x, y, image::Rgba([color, color, color, 255]));
+            }
+        }
+
+        assert_eq!(read_signed_number(&section).map(|n| n.value), Some(-12));
+    }
+}
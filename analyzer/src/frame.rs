@@ -0,0 +1,167 @@
+use crate::region_cache::RegionCache;
+use crate::util::is_splitscreen;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, SubImage};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+// Every detector reads from the same 1280x720 frame, so it's prepared once
+// and shared. Anything more than one of them wants to know about the whole
+// frame is worked out the first time it's asked for, and kept.
+
+pub const WIDTH: u32 = 1280;
+pub const HEIGHT: u32 = 720;
+
+/// A frame, ready for the detectors. It dereferences to the image, so they
+/// can crop from it like any other.
+#[derive(Debug)]
+pub struct Frame {
+    image: DynamicImage,
+    splitscreen: OnceLock<bool>,
+    regions: Option<Arc<RegionCache>>,
+}
+
+impl Frame {
+    /// Prepares a frame, resizing it if it isn't 1280x720 already. Captures
+    /// at 16:9 are shrunk directly, and anything else goes through `resize`.
+    pub fn new(image: &DynamicImage) -> Frame {
+        match image {
+            _ if image.dimensions() == (WIDTH, HEIGHT) => Frame::prepare(image.clone()),
+            DynamicImage::ImageRgb8(rgb) if rgb.width() * HEIGHT == rgb.height() * WIDTH => {
+                Frame::prepare(DynamicImage::ImageRgb8(shrink(rgb)))
+            }
+            _ => Frame::prepare(image.resize(WIDTH, HEIGHT, image::imageops::Nearest)),
+        }
+    }
+
+    fn prepare(image: DynamicImage) -> Frame {
+        let image = match image {
+            DynamicImage::ImageRgb8(_) => image,
+            other => DynamicImage::ImageRgb8(other.to_rgb8()),
+        };
+
+        Frame {
+            image,
+            splitscreen: OnceLock::new(),
+            regions: None,
+        }
+    }
+
+    /// Reads the HUD through `cache`, reusing what was read from the frames
+    /// before for any region that hasn't changed since
+    pub fn with_cache(self, cache: &Arc<RegionCache>) -> Frame {
+        Frame {
+            regions: Some(cache.clone()),
+            ..self
+        }
+    }
+
+    pub fn image(&self) -> &DynamicImage {
+        &self.image
+    }
+
+    /// The frame's pixels. Frames are always RGB once they're prepared.
+    pub fn rgb(&self) -> &RgbImage {
+        self.image
+            .as_rgb8()
+            .expect("prepared frames are always RGB")
+    }
+
+    /// Part of the frame, without copying it out like `crop_imm` does. Pass
+    /// it on as `&*view` - it's what the view derefs to that can be read
+    pub fn view(&self, x: u32, y: u32, width: u32, height: u32) -> SubImage<&RgbImage> {
+        image::imageops::crop_imm(self.rgb(), x, y, width, height)
+    }
+
+    /// Whether the screen is split into four player views
+    pub fn is_splitscreen(&self) -> bool {
+        *self.splitscreen.get_or_init(|| is_splitscreen(self))
+    }
+
+    /// A single pixel, at the same depth as `to_rgb16` would give, without
+    /// converting the whole frame to get it
+    pub fn rgb16_pixel(&self, x: u32, y: u32) -> Rgb<u16> {
+        let [r, g, b, _] = self.image.get_pixel(x, y).0;
+
+        // the same as `to_rgb16` scales them
+        Rgb([r, g, b].map(|c| c as u16 * 257))
+    }
+
+    /// What `read` gives for a region, from the cache if the frame has one
+    pub(crate) fn cached<T: Clone + Send + 'static>(
+        &self,
+        region: [u32; 4],
+        read: impl FnOnce() -> T,
+    ) -> T {
+        match &self.regions {
+            Some(cache) => cache.get_or_read(self.rgb(), region, read),
+            None => read(),
+        }
+    }
+}
+
+/// The same as resizing with `Nearest`, without going through floats for
+/// every pixel on the way. With no support either side, that picks the pixel
+/// the middle of each new one falls in, so this does the same.
+fn shrink(image: &RgbImage) -> RgbImage {
+    let source = |to: u32, from: u32| -> Vec<u32> {
+        let ratio = from as f32 / to as f32;
+        (0..to)
+            .map(|i| (((i as f32 + 0.5) * ratio).floor() as u32).min(from - 1))
+            .collect()
+    };
+    let xs = source(WIDTH, image.width());
+    let ys = source(HEIGHT, image.height());
+
+    RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        *image.get_pixel(xs[x as usize], ys[y as usize])
+    })
+}
+
+impl From<DynamicImage> for Frame {
+    /// Takes the image as it is when it's already 1280x720, without copying it
+    fn from(image: DynamicImage) -> Frame {
+        if image.dimensions() == (WIDTH, HEIGHT) {
+            Frame::prepare(image)
+        } else {
+            Frame::new(&image)
+        }
+    }
+}
+
+impl Deref for Frame {
+    type Target = DynamicImage;
+
+    fn deref(&self) -> &DynamicImage {
+        &self.image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Frame;
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    #[test]
+    fn frames_are_resized_once() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(1920, 1080, Rgb([10, 20, 30])));
+
+        let frame = Frame::new(&image);
+
+        assert_eq!((frame.width(), frame.height()), (1280, 720));
+        assert_eq!(frame.rgb16_pixel(0, 0), frame.to_rgb16()[(0, 0)]);
+    }
+
+    #[test]
+    fn shrinking_matches_resizing() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs/test_1.jpg");
+        let image = image::open(path).unwrap();
+        assert_eq!((image.width(), image.height()), (1920, 1080));
+
+        let frame = Frame::new(&image);
+
+        assert_eq!(
+            frame.image(),
+            &image.resize(1280, 720, image::imageops::Nearest)
+        );
+    }
+}
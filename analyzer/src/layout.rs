@@ -0,0 +1,160 @@
+use crate::color::max_color_diff;
+use crate::frame::Frame;
+use crate::screens::{intro, loading, match_result, race, race_result};
+
+// Where each kind of reference is cut from the frame, and what's done to it
+// before it's hashed, so new references can be made the same way the
+// detectors read them.
+
+/// Where the references are, for the tools that add to them
+pub const REFERENCES_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/references");
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Preprocess {
+    None,
+    Grayscale,
+    /// `max_color_diff` with the given cutoff
+    MaxColorDiff(u16),
+}
+
+#[derive(Debug, Clone)]
+pub struct ReferenceRegion {
+    pub name: &'static str,
+    /// `[x, y, width, height]` in the 1280x720 frame, one for each player's
+    /// quadrant, or just one when it's the same for everyone
+    pub crops: Vec<[u32; 4]>,
+    pub preprocess: Preprocess,
+    /// Where its references are kept, under `references/`
+    pub directory: &'static str,
+    pub extension: &'static str,
+}
+
+impl ReferenceRegion {
+    /// Whether there's a different crop for each player
+    pub fn per_player(&self) -> bool {
+        self.crops.len() > 1
+    }
+
+    /// Cuts the region out of a frame, for the player at `index` (from 0),
+    /// prepared the way the detector prepares it before hashing
+    pub fn cut(&self, frame: &image::DynamicImage, index: usize) -> Option<image::DynamicImage> {
+        let [x, y, width, height] = if self.per_player() {
+            *self.crops.get(index)?
+        } else {
+            self.crops[0]
+        };
+
+        let mut image = Frame::new(frame).crop_imm(x, y, width, height);
+
+        match self.preprocess {
+            Preprocess::None => {}
+            Preprocess::Grayscale => image = image.grayscale(),
+            Preprocess::MaxColorDiff(cutoff) => max_color_diff(&mut image, cutoff),
+        }
+
+        Some(image)
+    }
+}
+
+fn quadrants(corners: [[u32; 2]; 4], [width, height]: [u32; 2]) -> Vec<[u32; 4]> {
+    corners
+        .iter()
+        .map(|[x, y]| [*x, *y, width, height])
+        .collect()
+}
+
+/// Every region there are references for
+pub fn reference_regions() -> Vec<ReferenceRegion> {
+    vec![
+        ReferenceRegion {
+            name: "position",
+            crops: quadrants(race::POSITION_CROP, race::POSITION_SIZE),
+            preprocess: Preprocess::Grayscale,
+            directory: "race",
+            extension: "png",
+        },
+        ReferenceRegion {
+            name: "item",
+            crops: quadrants(race::ITEM_CROP, race::ITEM_SIZE),
+            preprocess: Preprocess::None,
+            directory: "items",
+            extension: "jpg",
+        },
+        ReferenceRegion {
+            name: "lap_flag",
+            crops: quadrants(race::LAP_FLAG_CROP, race::LAP_FLAG_SIZE),
+            preprocess: Preprocess::None,
+            directory: "race",
+            extension: "jpg",
+        },
+        ReferenceRegion {
+            name: "finished",
+            crops: quadrants(race::FINISH_CROP, race::FINISH_SIZE),
+            preprocess: Preprocess::None,
+            directory: "race",
+            extension: "jpg",
+        },
+        ReferenceRegion {
+            name: "go",
+            crops: vec![race::GO_CROP],
+            preprocess: Preprocess::None,
+            directory: "race",
+            extension: "jpg",
+        },
+        ReferenceRegion {
+            name: "speed",
+            crops: vec![match_result::SPEED_CROP],
+            preprocess: Preprocess::None,
+            directory: "match_result",
+            extension: "jpg",
+        },
+        ReferenceRegion {
+            name: "track",
+            crops: vec![intro::TRACK_CROP],
+            preprocess: Preprocess::MaxColorDiff(intro::TEXT_CUTOFF),
+            directory: "intro/tracks",
+            extension: "jpg",
+        },
+        ReferenceRegion {
+            name: "variant",
+            crops: vec![intro::VARIANT_CROP],
+            preprocess: Preprocess::MaxColorDiff(intro::TEXT_CUTOFF),
+            directory: "intro/variants",
+            extension: "png",
+        },
+        ReferenceRegion {
+            name: "character",
+            crops: vec![race_result::icon_region(0)],
+            preprocess: Preprocess::None,
+            directory: "characters",
+            extension: "png",
+        },
+        ReferenceRegion {
+            name: "loading",
+            crops: vec![loading::CROP],
+            preprocess: Preprocess::None,
+            directory: "loading",
+            extension: "jpg",
+        },
+    ]
+}
+
+/// The region called `name`
+pub fn reference_region(name: &str) -> Option<ReferenceRegion> {
+    reference_regions().into_iter().find(|r| r.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reference_regions;
+
+    #[test]
+    fn regions_are_inside_the_frame() {
+        for region in reference_regions() {
+            assert!(matches!(region.crops.len(), 1 | 4), "{}", region.name);
+            for [x, y, width, height] in region.crops {
+                assert!(x + width <= 1280 && y + height <= 720, "{}", region.name);
+            }
+        }
+    }
+}
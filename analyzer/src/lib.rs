@@ -1,34 +1,170 @@
+mod characters;
+mod classifier;
 mod color;
+mod digits;
+mod frame;
+mod glyphs;
 mod hasher;
+mod layout;
+mod ocr;
 mod reference;
+mod region_cache;
 mod screens;
+mod session;
+mod thresholds;
+mod time;
+mod trace;
 mod util;
 
+pub use classifier::Classifier;
+pub use frame::Frame;
 use image;
+pub use layout::{
+    reference_region, reference_regions, Preprocess, ReferenceRegion, REFERENCES_PATH,
+};
 pub use reference::Reference;
+pub use region_cache::RegionCache;
+pub use screens::race::alerts::Alert;
+pub use screens::race::events::{Incident, RaceIncident};
+#[cfg(feature = "minimap")]
+pub use screens::race::minimap;
 pub use screens::*;
+pub use session::{RaceOutcome, Session, SessionEvent};
+pub use thresholds::{samples, thresholds, Sample, MANIFEST_PATH};
+pub use time::RaceTime;
+pub use trace::{analyze_with_trace, Check, Decision, Trace};
 
 pub use screens::Screen;
 
+pub type Compare = fn(&Frame) -> bool;
+pub type Process = fn(&Frame) -> Option<Screen>;
+pub type Regions = fn() -> Vec<[u32; 4]>;
+
+/// Every screen's detector, in the order `analyze` checks them. The pause and
+/// highlights menus are drawn over the top of a race (or what looks like one),
+/// so they need to be checked first, and replays have the same split screen as
+/// a race, so they go before it too. The time trial screens have to read a
+/// whole timer to tell if they match, so they wait until the screens that are
+/// quicker to rule out have had a go.
+///
+/// The VS rules, online lobby and course vote screens aren't checked yet.
+/// There's no frame of any of them in the corpus, so where their text is drawn
+/// is a guess, and they stay out until there are frames to check them against.
+pub const SCREENS: [(&str, Compare, Process, Regions); 13] = [
+    (
+        "pause",
+        pause::Pause::compare,
+        pause::Pause::process,
+        pause::Pause::regions,
+    ),
+    (
+        "highlights",
+        highlights::Highlights::compare,
+        highlights::Highlights::process,
+        highlights::Highlights::regions,
+    ),
+    (
+        "replay",
+        replay::Replay::compare,
+        replay::Replay::process,
+        replay::Replay::regions,
+    ),
+    (
+        "race",
+        race::Race::compare,
+        race::Race::process,
+        race::Race::regions,
+    ),
+    (
+        "main_menu",
+        main_menu::MainMenu::compare,
+        main_menu::MainMenu::process,
+        main_menu::MainMenu::regions,
+    ),
+    (
+        "race_result",
+        race_result::RaceResult::compare,
+        race_result::RaceResult::process,
+        race_result::RaceResult::regions,
+    ),
+    (
+        "select_character",
+        select_character::SelectCharacter::compare,
+        select_character::SelectCharacter::process,
+        select_character::SelectCharacter::regions,
+    ),
+    (
+        "loading",
+        loading::Loading::compare,
+        loading::Loading::process,
+        loading::Loading::regions,
+    ),
+    (
+        "intro",
+        intro::Intro::compare,
+        intro::Intro::process,
+        intro::Intro::regions,
+    ),
+    (
+        "match_result",
+        match_result::MatchResult::compare,
+        match_result::MatchResult::process,
+        match_result::MatchResult::regions,
+    ),
+    (
+        "time_trial_result",
+        time_trial_result::TimeTrialResult::compare,
+        time_trial_result::TimeTrialResult::process,
+        time_trial_result::TimeTrialResult::regions,
+    ),
+    (
+        "time_trial",
+        time_trial::TimeTrial::compare,
+        time_trial::TimeTrial::process,
+        time_trial::TimeTrial::regions,
+    ),
+    (
+        "select_course",
+        select_course::SelectCourse::compare,
+        select_course::SelectCourse::process,
+        select_course::SelectCourse::regions,
+    ),
+];
+
 pub fn analyze(frame: &image::DynamicImage) -> Option<Screen> {
-    let resized = frame.resize(1280, 720, image::imageops::Nearest);
-    let res = if race::Race::compare(&resized) {
-        race::Race::process(&resized)
-    } else if main_menu::MainMenu::compare(&resized) {
-        main_menu::MainMenu::process(&resized)
-    } else if race_result::RaceResult::compare(&resized) {
-        race_result::RaceResult::process(&resized)
-    } else if select_character::SelectCharacter::compare(&resized) {
-        select_character::SelectCharacter::process(&resized)
-    } else if loading::Loading::compare(&resized) {
-        loading::Loading::process(&resized)
-    } else if intro::Intro::compare(&resized) {
-        intro::Intro::process(&resized)
-    } else if match_result::MatchResult::compare(&resized) {
-        match_result::MatchResult::process(&resized)
-    } else {
-        Some(Screen::Unknown)
-    };
-
-    res
+    analyze_frame(&Frame::new(frame))
+}
+
+/// The same as `analyze`, for a frame that's already been prepared
+pub fn analyze_frame(frame: &Frame) -> Option<Screen> {
+    detect(frame, |_, _| {})
+}
+
+/// Hashes every reference image now. Otherwise each one is hashed the first
+/// time it's needed, which makes the first frame of each screen slow.
+pub fn load_references() {
+    thresholds();
+    characters::load_references();
+    intro::load_references();
+    loading::load_references();
+    main_menu::load_references();
+    match_result::load_references();
+    ocr::load_references();
+    race::load_references();
+    select_character::load_references();
+}
+
+/// Goes through the screens until one matches, telling `checked` about each
+/// one it tries
+pub(crate) fn detect(frame: &Frame, mut checked: impl FnMut(&'static str, bool)) -> Option<Screen> {
+    for (name, compare, process, _) in SCREENS {
+        let matched = compare(frame);
+        checked(name, matched);
+
+        if matched {
+            return process(frame);
+        }
+    }
+
+    Some(Screen::Unknown)
 }
@@ -0,0 +1,459 @@
+use crate::color::{light_text_cutoff, max_color_diff, max_darkness};
+use image::{imageops, DynamicImage, GrayImage};
+use lazy_static::lazy_static;
+use std::ops::Range;
+
+// Reads short lines of text, like the player names on the results screens,
+// one character at a time. Characters are found by the gaps between them, then
+// compared with characters cut the same way from frames where the text is
+// known - the names on the results screens, the course names on four of the
+// intros, and the positions down the side of the match results. Each reference
+// has every time that character showed up there side by side, since it's drawn
+// a little differently on each screen and in each colour.
+//
+// That covers every digit, but none of the corpus has an A, F, H, J, M, O, Q,
+// U, X or Z in it, or an f, j, m, q, v, x or z, so those come out as `?` until
+// there's a frame to cut them from.
+
+// dark text is darker than this in every channel
+const DARK_TEXT_CUTOFF: u8 = 110;
+// runs of ink with fewer pixels than this are noise
+const MIN_INK: usize = 12;
+// text shorter than this is too small to make out, or is just noise
+const MIN_TEXT_HEIGHT: u32 = 8;
+// rows of text have at least this much of the ink of the busiest row, where
+// noise above and below it doesn't
+const TOP_INK: f32 = 0.03;
+// rows below the baseline have less than this much of the ink of the busiest
+// row
+const BASELINE_INK: f32 = 0.4;
+// a gap this much of the line height wide is a space, rather than the gap
+// between two letters
+const SPACE_WIDTH: f32 = 0.35;
+// characters in the game font are rarely wider than this much of the line
+// height, so anything wider is more than one character touching
+const MAX_CHAR_WIDTH: f32 = 1.4;
+const CHAR_WIDTH: f32 = 0.7;
+// characters are compared at this size, once they've been centred in a box as
+// wide as the line is tall
+const GLYPH_SIZE: u32 = 20;
+// how many rows the top or bottom of a line can be out by
+const LINE_SLACK: i32 = 1;
+// characters that match worse than this are read as `?`
+const MIN_CONFIDENCE: f32 = 0.8;
+// the longest character name is 20 characters, with its colour, like
+// "Shy Guy (Light Blue)"
+const MAX_LENGTH: usize = 20;
+// the longest course name is 25 characters
+const MAX_LONG_LENGTH: usize = 32;
+
+struct Glyph {
+    character: char,
+    image: GrayImage,
+}
+
+macro_rules! load_glyph {
+    ($character:expr, $file:expr $(,)?) => {{
+        let f = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/references/glyphs/",
+            $file
+        ));
+        let strip = image::load_from_memory(f).unwrap().to_luma8();
+
+        (0..strip.width() / GLYPH_SIZE)
+            .map(|i| Glyph {
+                character: $character,
+                image: imageops::crop_imm(&strip, i * GLYPH_SIZE, 0, GLYPH_SIZE, GLYPH_SIZE)
+                    .to_image(),
+            })
+            .collect::<Vec<_>>()
+    }};
+}
+
+lazy_static! {
+    static ref GLYPHS: Vec<Glyph> = [
+        load_glyph!('(', "open-bracket.png"),
+        load_glyph!(')', "close-bracket.png"),
+        load_glyph!('0', "digit-0.png"),
+        load_glyph!('1', "digit-1.png"),
+        load_glyph!('2', "digit-2.png"),
+        load_glyph!('3', "digit-3.png"),
+        load_glyph!('4', "digit-4.png"),
+        load_glyph!('5', "digit-5.png"),
+        load_glyph!('6', "digit-6.png"),
+        load_glyph!('7', "digit-7.png"),
+        load_glyph!('8', "digit-8.png"),
+        load_glyph!('9', "digit-9.png"),
+        load_glyph!('B', "upper-b.png"),
+        load_glyph!('C', "upper-c.png"),
+        load_glyph!('D', "upper-d.png"),
+        load_glyph!('E', "upper-e.png"),
+        load_glyph!('G', "upper-g.png"),
+        load_glyph!('I', "upper-i.png"),
+        load_glyph!('K', "upper-k.png"),
+        load_glyph!('L', "upper-l.png"),
+        load_glyph!('N', "upper-n.png"),
+        load_glyph!('P', "upper-p.png"),
+        load_glyph!('R', "upper-r.png"),
+        load_glyph!('S', "upper-s.png"),
+        load_glyph!('T', "upper-t.png"),
+        load_glyph!('V', "upper-v.png"),
+        load_glyph!('W', "upper-w.png"),
+        load_glyph!('Y', "upper-y.png"),
+        load_glyph!('a', "lower-a.png"),
+        load_glyph!('b', "lower-b.png"),
+        load_glyph!('c', "lower-c.png"),
+        load_glyph!('d', "lower-d.png"),
+        load_glyph!('e', "lower-e.png"),
+        load_glyph!('g', "lower-g.png"),
+        load_glyph!('h', "lower-h.png"),
+        load_glyph!('i', "lower-i.png"),
+        load_glyph!('k', "lower-k.png"),
+        load_glyph!('l', "lower-l.png"),
+        load_glyph!('n', "lower-n.png"),
+        load_glyph!('o', "lower-o.png"),
+        load_glyph!('p', "lower-p.png"),
+        load_glyph!('r', "lower-r.png"),
+        load_glyph!('s', "lower-s.png"),
+        load_glyph!('t', "lower-t.png"),
+        load_glyph!('u', "lower-u.png"),
+        load_glyph!('w', "lower-w.png"),
+        load_glyph!('y', "lower-y.png"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+}
+
+pub(crate) fn load_references() {
+    lazy_static::initialize(&GLYPHS);
+}
+
+/// Reads a single line of text, like a name. Players' rows have dark text on
+/// their colour, and everyone else's is light text on a see-through
+/// background. Gives up if any of it can't be made out.
+pub(crate) fn read_text(section: &DynamicImage, dark_text: bool) -> Option<String> {
+    read_line(section, dark_text, |text| {
+        // a name that's only partly made out is as good as a wrong one. names
+        // always start with a capital too, where the end of one that's still
+        // fading in might not
+        text.chars().count() <= MAX_LENGTH
+            && !text.contains('?')
+            && text.starts_with(char::is_uppercase)
+    })
+}
+
+/// Like `read_text`, for text that's longer than a name, like a course name.
+/// Characters that can't be made out are read as `?`, to be matched up with
+/// the whole name afterwards
+pub(crate) fn read_long_text(section: &DynamicImage, dark_text: bool) -> Option<String> {
+    read_line(section, dark_text, |text| {
+        text.chars().count() <= MAX_LONG_LENGTH
+    })
+}
+
+fn read_line(
+    section: &DynamicImage,
+    dark_text: bool,
+    accept: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let dark = dark_text.then(|| {
+        let mut section = section.clone();
+        max_darkness(&mut section, DARK_TEXT_CUTOFF);
+        read_ink(&section).filter(|text| accept(text))
+    });
+
+    dark.flatten().or_else(|| {
+        let mut section = section.clone();
+        let cutoff = light_text_cutoff(&section);
+        max_color_diff(&mut section, cutoff);
+        read_ink(&section).filter(|text| accept(text))
+    })
+}
+
+/// Reads the text out of a section where it's drawn in white
+fn read_ink(section: &DynamicImage) -> Option<String> {
+    let read: Vec<char> = cells(section)?
+        .iter()
+        .map(|cell| match cell {
+            None => ' ',
+            Some(cell) => match best_char(cell) {
+                Some((c, score)) if score >= MIN_CONFIDENCE => c,
+                _ => '?',
+            },
+        })
+        .collect();
+
+    // a capital I and a lower case l are drawn almost the same, so they're
+    // told apart by where they are in the word instead
+    let text: String = read
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let after_letter = i > 0 && read[i - 1].is_alphabetic();
+            match c {
+                'I' if after_letter => 'l',
+                'l' if !after_letter => 'I',
+                c => *c,
+            }
+        })
+        .collect();
+
+    // noise comes out as a jumble of `?`s and punctuation, where text is
+    // mostly letters
+    let length = text.chars().filter(|c| *c != ' ').count();
+    let letters = text.chars().filter(|c| c.is_alphanumeric()).count();
+    if letters == 0 || letters * 2 < length {
+        return None;
+    }
+
+    Some(text)
+}
+
+/// Cuts a line of text up into one image per character, each as tall as the
+/// line. Spaces come out as `None`
+fn cells(section: &DynamicImage) -> Option<Vec<Option<GrayImage>>> {
+    let mut luma = section.to_luma8();
+    erase_lines(&mut luma);
+    let (width, height) = luma.dimensions();
+    let ink = |x: u32, y: u32| luma.get_pixel(x, y).0[0] >= 128;
+
+    // text always starts at the left, so anything after a gap as wide as the
+    // section is tall is something else in the row
+    let mut text = runs(0..width, 0..height, ink).into_iter();
+    let first = text.next()?;
+    let mut end = first[0] + first[1];
+    for [x, width] in text {
+        if x - end > height {
+            break;
+        }
+        end = x + width;
+    }
+    let text = first[0]..end;
+
+    // the line runs from the top of the capitals down to the baseline, so
+    // lower case letters keep their place relative to them. that's the block
+    // of rows with the most ink, apart from anything above or below it. only
+    // a few letters hang below the baseline, so those rows have much less ink
+    // than the rest
+    let counts: Vec<usize> = (0..height)
+        .map(|y| text.clone().filter(|x| ink(*x, y)).count())
+        .collect();
+    let busiest = *counts.iter().max()?;
+    let inked = |y: usize| counts[y] as f32 >= busiest as f32 * TOP_INK;
+    let mut block = 0..0;
+    let mut y = 0;
+    while y < counts.len() {
+        let start = y;
+        while y < counts.len() && inked(y) {
+            y += 1;
+        }
+        let total = |rows: &Range<usize>| counts[rows.clone()].iter().sum::<usize>();
+        if total(&(start..y)) > total(&block) {
+            block = start..y;
+        }
+        y += 1;
+    }
+    let top = block.start as u32;
+    let baseline = block
+        .rev()
+        .find(|y| counts[*y] as f32 >= busiest as f32 * BASELINE_INK)? as u32;
+    let line_height = (baseline + 1).saturating_sub(top);
+    if line_height < MIN_TEXT_HEIGHT {
+        return None;
+    }
+
+    // each character's left edge and width, going by the gaps between them
+    // along the line. a space is marked with no width
+    let mut cells: Vec<[u32; 2]> = vec![];
+    let mut end = None;
+    for [x, width] in runs(text, top..baseline + 1, ink) {
+        if end.is_some_and(|end| (x - end) as f32 >= line_height as f32 * SPACE_WIDTH) {
+            cells.push([0, 0]);
+        }
+        cells.extend(split(x, width, line_height));
+        end = Some(x + width);
+    }
+
+    let cells = cells
+        .into_iter()
+        .map(|[x, width]| {
+            (width > 0).then(|| {
+                GrayImage::from_fn(width, line_height, |cx, cy| {
+                    *luma.get_pixel(x + cx, top + cy)
+                })
+            })
+        })
+        .collect();
+
+    Some(cells)
+}
+
+/// The left edge and width of each run of columns with ink in `rows`. Specks
+/// of noise, and bits of the background that make it through, have too little
+/// ink to be a character, so they're left out
+fn runs(columns: Range<u32>, rows: Range<u32>, ink: impl Fn(u32, u32) -> bool) -> Vec<[u32; 2]> {
+    let mut runs = vec![];
+    let mut left = None;
+    for x in columns.start..=columns.end {
+        let has_ink = x < columns.end && rows.clone().any(|y| ink(x, y));
+
+        match (has_ink, left) {
+            (true, None) => left = Some(x),
+            (false, Some(start)) => {
+                let total: usize = (start..x)
+                    .map(|x| rows.clone().filter(|y| ink(x, *y)).count())
+                    .sum();
+                if total >= MIN_INK {
+                    runs.push([start, x - start]);
+                }
+                left = None;
+            }
+            _ => {}
+        }
+    }
+
+    runs
+}
+
+/// Blanks out lines running across the section, like the edge of the row,
+/// which are longer than any stroke of a character
+fn erase_lines(luma: &mut GrayImage) {
+    let (width, height) = luma.dimensions();
+
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            let start = x;
+            while x < width && luma.get_pixel(x, y).0[0] >= 128 {
+                x += 1;
+            }
+
+            if x - start > height {
+                (start..x).for_each(|x| luma.put_pixel(x, y, image::Luma([0])));
+            }
+            x += 1;
+        }
+    }
+}
+
+/// Centres a character in a box as wide as the line is tall, so that narrow
+/// characters keep their shape, and scales it to `GLYPH_SIZE`
+fn normalize(cell: &GrayImage) -> GrayImage {
+    let width = cell.width().max(cell.height());
+    let mut boxed = GrayImage::new(width, cell.height());
+    imageops::overlay(&mut boxed, cell, ((width - cell.width()) / 2) as i64, 0);
+
+    imageops::resize(
+        &boxed,
+        GLYPH_SIZE,
+        GLYPH_SIZE,
+        imageops::FilterType::Triangle,
+    )
+}
+
+/// Picks the character drawn in `cell`, along with how alike they are from 0
+/// to 1
+fn best_char(cell: &GrayImage) -> Option<(char, f32)> {
+    if cell.width() == 0 || cell.height() == 0 {
+        return None;
+    }
+
+    // the top and bottom of the line can come out a row off, which changes
+    // the scale of every character on it, so the cell is also tried with each
+    // end moved a row either way
+    let slack = -LINE_SLACK..=LINE_SLACK;
+    let cells: Vec<GrayImage> = slack
+        .clone()
+        .flat_map(|top| slack.clone().map(move |bottom| (top, bottom)))
+        .filter_map(|(top, bottom)| {
+            let height = cell.height() as i32 - top - bottom;
+            (height > 0).then(|| {
+                normalize(&GrayImage::from_fn(cell.width(), height as u32, |x, y| {
+                    match u32::try_from(y as i32 + top) {
+                        Ok(y) if y < cell.height() => *cell.get_pixel(x, y),
+                        _ => image::Luma([0]),
+                    }
+                }))
+            })
+        })
+        .collect();
+    let most = (GLYPH_SIZE * GLYPH_SIZE) as f32 * u8::MAX as f32;
+
+    GLYPHS
+        .iter()
+        .filter_map(|glyph| {
+            let difference = cells
+                .iter()
+                .map(|cell| {
+                    cell.pixels()
+                        .zip(glyph.image.pixels())
+                        .map(|(a, b)| a.0[0].abs_diff(b.0[0]) as u32)
+                        .sum::<u32>()
+                })
+                .min()?;
+
+            Some((glyph.character, 1.0 - difference as f32 / most))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Splits a run of ink that's too wide to be one character into evenly sized
+/// pieces
+fn split(x: u32, width: u32, height: u32) -> Vec<[u32; 2]> {
+    if (width as f32) <= height as f32 * MAX_CHAR_WIDTH {
+        return vec![[x, width]];
+    }
+
+    let count = ((width as f32 / (height as f32 * CHAR_WIDTH)).round() as u32).max(1);
+    (0..count)
+        .map(|i| {
+            let start = x + i * width / count;
+            let end = x + (i + 1) * width / count;
+            [start, end - start]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_long_text, read_text};
+    use crate::frame::Frame;
+    use image::DynamicImage;
+
+    fn section(path: &str, [x, y, width, height]: [u32; 4]) -> DynamicImage {
+        let image = image::open(format!("{}/{path}", env!("CARGO_MANIFEST_DIR"))).unwrap();
+
+        Frame::new(&image).crop_imm(x, y, width, height)
+    }
+
+    #[test]
+    fn reads_text_that_glyphs_were_not_cut_from() {
+        // the course names on the intros, from courses whose intros weren't
+        // used to cut any of the glyphs
+        let name = [340, 612, 460, 56];
+
+        assert_eq!(
+            read_text(&section("tests/inputs/test_25.jpg", name), false).as_deref(),
+            Some("Water Park")
+        );
+        assert_eq!(
+            read_text(&section("tests/inputs/test_49.jpg", name), false).as_deref(),
+            Some("Peach Gardens")
+        );
+    }
+
+    #[test]
+    fn reads_digits() {
+        // the digits were cut from the positions down the side of the match
+        // results, so these are the positions on the race results instead.
+        // the two digits are drawn far enough apart to read as a space
+        let path = "spec-data/screens/race_result/totals.jpg";
+
+        for (row, position) in [(9, "1 0"), (10, "1 1"), (11, "1 2")] {
+            let section = section(path, [340, 54 + row * 52, 60, 34]);
+            assert_eq!(read_long_text(&section, false).as_deref(), Some(position));
+        }
+    }
+}
@@ -1,6 +1,14 @@
 use super::screens::Screen;
+use crate::frame::Frame;
 
 pub trait Reference {
-    fn process(frame: &image::DynamicImage) -> Option<Screen>;
-    fn compare(frame: &image::DynamicImage) -> bool;
+    fn process(frame: &Frame) -> Option<Screen>;
+    fn compare(frame: &Frame) -> bool;
+
+    /// The parts of the (1280x720) frame this screen is read from, as
+    /// `[x, y, width, height]`. Only used to show what we looked at when
+    /// reviewing results.
+    fn regions() -> Vec<[u32; 4]> {
+        vec![]
+    }
 }
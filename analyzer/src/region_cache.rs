@@ -0,0 +1,121 @@
+use image::{GenericImageView, RgbImage};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Most of the HUD stays put for seconds at a time, so reading it again on
+// every frame is mostly wasted. Each region's answer is kept along with the
+// pixels it was read from, and handed back for as long as the region still
+// looks like that. It's only ever compared with the pixels it was read from,
+// so slow changes can't creep past it a frame at a time.
+
+// capture noise moves a region by a point or two on average. A different
+// digit or item moves it by a lot more than this
+const UNCHANGED: u32 = 3;
+
+struct Entry {
+    pixels: RgbImage,
+    value: Box<dyn Any + Send>,
+}
+
+/// What was read from each region of the last frames, for frames that are
+/// prepared `with_cache`. Only useful to something that sees a stream of
+/// frames - `analyze` never uses one.
+#[derive(Default)]
+pub struct RegionCache {
+    entries: Mutex<HashMap<[u32; 4], Entry>>,
+}
+
+impl std::fmt::Debug for RegionCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let regions = self.entries.lock().map_or(0, |entries| entries.len());
+
+        f.debug_struct("RegionCache")
+            .field("regions", &regions)
+            .finish()
+    }
+}
+
+impl RegionCache {
+    pub fn new() -> Self {
+        RegionCache::default()
+    }
+
+    /// What `read` gave for this region last time, if it hasn't changed since.
+    /// Otherwise it's read again and kept.
+    pub(crate) fn get_or_read<T: Clone + Send + 'static>(
+        &self,
+        frame: &RgbImage,
+        [x, y, width, height]: [u32; 4],
+        read: impl FnOnce() -> T,
+    ) -> T {
+        let pixels = frame.view(x, y, width, height).to_image();
+
+        {
+            let entries = self.entries.lock().expect("the region cache was poisoned");
+            let kept = entries
+                .get(&[x, y, width, height])
+                .filter(|entry| difference(&entry.pixels, &pixels) <= UNCHANGED)
+                .and_then(|entry| entry.value.downcast_ref::<T>());
+            if let Some(value) = kept {
+                return value.clone();
+            }
+        }
+
+        // read without holding the lock, so the players can be read at once
+        let value = read();
+        self.entries
+            .lock()
+            .expect("the region cache was poisoned")
+            .insert(
+                [x, y, width, height],
+                Entry {
+                    pixels,
+                    value: Box::new(value.clone()),
+                },
+            );
+
+        value
+    }
+}
+
+/// How far apart two crops of the same region are, on average per channel
+fn difference(a: &RgbImage, b: &RgbImage) -> u32 {
+    let total: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum();
+
+    (total / a.as_raw().len().max(1) as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegionCache;
+    use image::{Rgb, RgbImage};
+
+    const REGION: [u32; 4] = [10, 10, 20, 20];
+
+    #[test]
+    fn unchanged_regions_are_not_read_again() {
+        let cache = RegionCache::new();
+        let frame = RgbImage::from_pixel(64, 64, Rgb([100, 100, 100]));
+        let mut noisy = frame.clone();
+        noisy.put_pixel(15, 15, Rgb([110, 90, 100]));
+
+        assert_eq!(cache.get_or_read(&frame, REGION, || 1), 1);
+        assert_eq!(cache.get_or_read(&noisy, REGION, || 2), 1);
+    }
+
+    #[test]
+    fn changed_regions_are_read_again() {
+        let cache = RegionCache::new();
+        let frame = RgbImage::from_pixel(64, 64, Rgb([100, 100, 100]));
+        let changed = RgbImage::from_pixel(64, 64, Rgb([200, 200, 200]));
+
+        assert_eq!(cache.get_or_read(&frame, REGION, || 1), 1);
+        assert_eq!(cache.get_or_read(&changed, REGION, || 2), 2);
+    }
+}
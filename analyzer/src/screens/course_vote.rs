@@ -0,0 +1,135 @@
+use super::select_course::find_course;
+use super::Screen;
+use crate::color::{average_colors, lightness, player_color};
+use crate::frame::Frame;
+use crate::ocr::{read_long_text, read_text};
+use crate::reference::Reference;
+use serde::Serialize;
+
+// Before an online race, everyone's vote is listed down the right of the
+// screen - who voted, and the course they picked. A roulette then runs up and
+// down the list, and the row it stops on lights up. Rows are laid out like
+// the results table, in the players' colours for the players on this console.
+//
+// There's no frame of the vote in the corpus either. The vote list replaces
+// the lobby's list of players, so its rows are taken to start in the same
+// place as `online_lobby`'s, with the course where the lobby has the VR. None
+// of it has been measured, so the vote isn't in `SCREENS` until there's a
+// frame to check it against.
+const ROW_ORIGIN: [u32; 2] = [640, 70];
+const ROW_HEIGHT: u32 = 48;
+const ROW_MARGIN: u32 = 4;
+const ROWS: u32 = 12;
+const COLOR_CROP: [u32; 2] = [4, 2];
+const NAME_CROP: [u32; 2] = [60, 7];
+const NAME_WIDTH: u32 = 200;
+const COURSE_CROP: [u32; 2] = [270, 7];
+const COURSE_WIDTH: u32 = 330;
+const TEXT_HEIGHT: u32 = 34;
+const PICKED_LIGHTNESS: u16 = 60_000;
+// a couple of rows need to name a course before we believe this is the vote
+const MIN_VOTES: usize = 2;
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct CourseVote {
+    votes: Vec<Vote>,
+
+    /// The course the roulette stopped on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    winner: Option<&'static str>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct Vote {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(
+        serialize_with = "super::serialize_player",
+        skip_serializing_if = "Option::is_none"
+    )]
+    player: Option<u8>,
+
+    /// The course voted for. Random votes don't have one until the roulette
+    /// lands on them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    course: Option<&'static str>,
+}
+
+impl Reference for CourseVote {
+    fn compare(frame: &Frame) -> bool {
+        if frame.is_splitscreen() {
+            return false;
+        }
+
+        (0..ROWS)
+            .filter(|i| read_course(frame, row_top(*i), false).is_some())
+            .take(MIN_VOTES)
+            .count()
+            == MIN_VOTES
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let mut winner = None;
+        let votes: Vec<Vote> = (0..ROWS)
+            .filter_map(|i| {
+                let top = row_top(i);
+                let [x, y] = COLOR_CROP;
+                let color = average_colors(&*frame.view(ROW_ORIGIN[0] + x, top + y, 40, 4));
+                let picked = lightness(&image::Rgb(color)) > PICKED_LIGHTNESS;
+                let player = if picked { None } else { player_color(color) };
+
+                let [x, y] = NAME_CROP;
+                let name = frame.crop_imm(ROW_ORIGIN[0] + x, top + y, NAME_WIDTH, TEXT_HEIGHT);
+                let name = read_text(&name, player.is_some() || picked);
+                let course = read_course(frame, top, player.is_some() || picked);
+
+                if name.is_none() && course.is_none() {
+                    return None;
+                }
+                if picked {
+                    winner = course;
+                }
+
+                Some(Vote {
+                    name,
+                    player,
+                    course,
+                })
+            })
+            .collect();
+
+        if votes.is_empty() {
+            return None;
+        }
+
+        Some(Screen::CourseVote(CourseVote { votes, winner }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        (0..ROWS)
+            .flat_map(|i| {
+                let top = row_top(i);
+                let row =
+                    |[x, y]: [u32; 2], width, height| [ROW_ORIGIN[0] + x, top + y, width, height];
+
+                [
+                    row(COLOR_CROP, 40, 4),
+                    row(NAME_CROP, NAME_WIDTH, TEXT_HEIGHT),
+                    row(COURSE_CROP, COURSE_WIDTH, TEXT_HEIGHT),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn row_top(i: u32) -> u32 {
+    ROW_ORIGIN[1] + i * (ROW_HEIGHT + ROW_MARGIN)
+}
+
+fn read_course(frame: &image::DynamicImage, top: u32, dark_text: bool) -> Option<&'static str> {
+    let [x, y] = COURSE_CROP;
+    let section = frame.crop_imm(ROW_ORIGIN[0] + x, top + y, COURSE_WIDTH, TEXT_HEIGHT);
+
+    read_long_text(&section, dark_text).and_then(|text| find_course(&text))
+}
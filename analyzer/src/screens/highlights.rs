@@ -0,0 +1,99 @@
+use super::Screen;
+use crate::color::{average_colors, get_overall_average, mostly_yellow};
+use crate::frame::Frame;
+use crate::reference::Reference;
+use serde::Serialize;
+
+// Once everyone has finished, the race carries on behind a menu offering the
+// next race, the highlight reel, or quitting. The reel is race footage, so
+// this menu is the only thing that tells it apart from a race. The selected
+// option has yellow chevrons at both ends of its bar, which is darker than the
+// others. The results table has yellow rows in the same place, but they're
+// yellow all the way across.
+const OPTIONS: [HighlightsOption; 3] = [
+    HighlightsOption::NextRace,
+    HighlightsOption::HighlightReel,
+    HighlightsOption::Quit,
+];
+const LEFT_CHEVRON: [u32; 2] = [304, 240];
+const RIGHT_CHEVRON: [u32; 2] = [968, 240];
+const CHEVRON_SIZE: u32 = 6;
+const BAR_CROP: [u32; 4] = [360, 250, 40, 6];
+const MAX_BAR_AVERAGE: u32 = 30_000;
+const OPTION_SPACING: u32 = 100;
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightsOption {
+    NextRace,
+    HighlightReel,
+    Quit,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct Highlights {
+    pub selected: HighlightsOption,
+}
+
+impl Reference for Highlights {
+    fn compare(frame: &Frame) -> bool {
+        get_selected(frame).is_some()
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let selected = get_selected(frame)?;
+
+        Some(Screen::Highlights(Highlights { selected }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        (0..OPTIONS.len() as u32)
+            .flat_map(|i| {
+                let offset = i * OPTION_SPACING;
+                let [x, y, width, height] = BAR_CROP;
+
+                [
+                    [
+                        LEFT_CHEVRON[0],
+                        LEFT_CHEVRON[1] + offset,
+                        CHEVRON_SIZE,
+                        CHEVRON_SIZE,
+                    ],
+                    [
+                        RIGHT_CHEVRON[0],
+                        RIGHT_CHEVRON[1] + offset,
+                        CHEVRON_SIZE,
+                        CHEVRON_SIZE,
+                    ],
+                    [x, y + offset, width, height],
+                ]
+            })
+            .collect()
+    }
+}
+
+fn get_selected(frame: &Frame) -> Option<HighlightsOption> {
+    let is_yellow = |[x, y]: [u32; 2]| {
+        mostly_yellow(average_colors(&*frame.view(
+            x,
+            y,
+            CHEVRON_SIZE,
+            CHEVRON_SIZE,
+        )))
+    };
+
+    let mut highlighted = OPTIONS.iter().enumerate().filter(|(i, _)| {
+        let offset = *i as u32 * OPTION_SPACING;
+        let [x, y, width, height] = BAR_CROP;
+        let bar = frame.view(x, y + offset, width, height);
+
+        get_overall_average(&*bar) < MAX_BAR_AVERAGE
+            && is_yellow([LEFT_CHEVRON[0], LEFT_CHEVRON[1] + offset])
+            && is_yellow([RIGHT_CHEVRON[0], RIGHT_CHEVRON[1] + offset])
+    });
+
+    match (highlighted.next(), highlighted.next()) {
+        (Some((_, option)), None) => Some(*option),
+        _ => None,
+    }
+}
@@ -1,8 +1,10 @@
 use crate::color::{average_colors, max_color_diff};
+use crate::frame::Frame;
 use crate::hasher;
 use crate::load_reference_hash;
 use crate::reference::Reference;
 use crate::screens::Screen;
+use crate::thresholds::{threshold, Sample};
 use lazy_static::lazy_static;
 use log::{error, info};
 use rayon::prelude::*;
@@ -15,6 +17,9 @@ pub struct VariantGroup<'a> {
 
 pub struct IntroReference<'a> {
     pub name: &'a str,
+    /// The cup the course is in, and where it comes in that cup, if it's in
+    /// one
+    pub cup: Option<(&'a str, usize)>,
     pub reference: image_hasher::ImageHash,
 }
 
@@ -27,46 +32,57 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Alpine Pass (3DS)",
+                    cup: None,
                     reference: load_reference_hash!("intro/tracks/alpine_pass_3ds.jpg"),
                 },
                 IntroReference {
                     name: "DK Jungle (3DS)",
+                    cup: Some(("Banana Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/dk_jungle_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Koopa City (3DS)",
+                    cup: None,
                     reference: load_reference_hash!("intro/tracks/koopa_city_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Melody Motorway (3DS)",
+                    cup: None,
                     reference: load_reference_hash!("intro/tracks/melody_motorway_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Music Park (3DS)",
+                    cup: Some(("Leaf Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/music_park_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Neo Bowser City (3DS)",
+                    cup: Some(("Bell Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/neo_bowser_city_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Piranha Plant Slide (3DS)",
+                    cup: Some(("Lightning Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/piranha_plant_slide_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Rainbow Road (3DS)",
+                    cup: Some(("Moon Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/rainbow_road_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Rock Rock Mountain (3DS)",
+                    cup: Some(("Rock Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/rock_rock_mountain_3ds.jpg"),
                 },
                 IntroReference {
                     name: "Rosalina’s Ice World (3DS)",
+                    cup: Some(("Spiny Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/rosalina_s_ice_world.jpg"),
                 },
                 IntroReference {
                     name: "Toad Circuit (3DS)",
+                    cup: Some(("Golden Dash Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/toad_circuit_3ds.jpg"),
                 },
             ],
@@ -76,30 +92,37 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Cheep Cheep Beach (DS)",
+                    cup: Some(("Shell Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/cheep_cheep_beach_ds.jpg"),
                 },
                 IntroReference {
                     name: "Mario Circuit (DS)",
+                    cup: Some(("Boomerang Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/mario_circuit_ds.jpg"),
                 },
                 IntroReference {
                     name: "Peach Gardens (DS)",
+                    cup: Some(("Moon Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/peach_gardens_ds.jpg"),
                 },
                 IntroReference {
                     name: "Shroom Ridge (DS)",
+                    cup: Some(("Lucky Cat Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/shroom_ridge_ds.jpg"),
                 },
                 IntroReference {
                     name: "Tick-Tock Clock (DS)",
+                    cup: Some(("Lightning Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/tick-tock_clock_ds.jpg"),
                 },
                 IntroReference {
                     name: "Waluigi Pinball (DS)",
+                    cup: Some(("Turnip Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/waluigi_pinball_ds.jpg"),
                 },
                 IntroReference {
                     name: "Wario Stadium (DS)",
+                    cup: Some(("Leaf Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/wario_stadium_ds.jpg"),
                 },
             ],
@@ -109,34 +132,42 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Boo Lake (GBA)",
+                    cup: Some(("Rock Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/boo_lake_gba.jpg"),
                 },
                 IntroReference {
                     name: "Cheese Land (GBA)",
+                    cup: Some(("Crossing Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/cheese_land_gba.jpg"),
                 },
                 IntroReference {
                     name: "Mario Circuit (GBA)",
+                    cup: Some(("Shell Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/mario_circuit_gba.jpg"),
                 },
                 IntroReference {
                     name: "Ribbon Road (GBA)",
+                    cup: Some(("Bell Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/ribbon_road_gba.jpg"),
                 },
                 IntroReference {
                     name: "Riverside Park (GBA)",
+                    cup: Some(("Fruit Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/riverside_park_gba.jpg"),
                 },
                 IntroReference {
                     name: "Sky Garden (GBA)",
+                    cup: Some(("Lucky Cat Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/sky_garden_gba.jpg"),
                 },
                 IntroReference {
                     name: "Snow Land (GBA)",
+                    cup: Some(("Propeller Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/snow_land_gba.jpg"),
                 },
                 IntroReference {
                     name: "Sunset Wilds (GBA)",
+                    cup: Some(("Cherry Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/sunset_wilds_gba.jpg"),
                 },
             ],
@@ -146,30 +177,37 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Baby Park (GameCube)",
+                    cup: Some(("Crossing Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/baby_park_gcn.jpg"),
                 },
                 IntroReference {
                     name: "Daisy Cruiser (GameCube)",
+                    cup: Some(("Feather Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/daisy_cruiser_gcn.jpg"),
                 },
                 IntroReference {
                     name: "DK Mountain (GameCube)",
+                    cup: Some(("Acorn Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/dk_mountain.jpg"),
                 },
                 IntroReference {
                     name: "Dry Dry Desert (GameCube)",
+                    cup: Some(("Banana Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/dry_dry_desert_gcn.jpg"),
                 },
                 IntroReference {
                     name: "Sherbet Land (GameCube)",
+                    cup: Some(("Leaf Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/sherbet_land_gcn.jpg"),
                 },
                 IntroReference {
                     name: "Waluigi Stadium (GameCube)",
+                    cup: Some(("Boomerang Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/waluigi_stadium_gcn.jpg"),
                 },
                 IntroReference {
                     name: "Yoshi Circuit (GameCube)",
+                    cup: Some(("Egg Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/yoshi_circuit_gcn.jpg"),
                 },
             ],
@@ -179,26 +217,32 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Choco Mountain (N64)",
+                    cup: Some(("Golden Dash Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/choco_mountain_n64.jpg"),
                 },
                 IntroReference {
                     name: "Kalimari Desert (N64)",
+                    cup: Some(("Turnip Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/kalimari_desert_n64.jpg"),
                 },
                 IntroReference {
                     name: "Rainbow Road (N64)",
+                    cup: Some(("Lightning Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/rainbow_road_n64.jpg"),
                 },
                 IntroReference {
                     name: "Royal Raceway (N64)",
+                    cup: Some(("Banana Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/royal_raceway_n64.jpg"),
                 },
                 IntroReference {
                     name: "Toad's Turnpike (N64)",
+                    cup: Some(("Shell Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/toads_turnpike_n64.jpg"),
                 },
                 IntroReference {
                     name: "Yoshi Valley (N64)",
+                    cup: Some(("Leaf Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/yoshi_valley_n64.jpg"),
                 },
             ],
@@ -208,126 +252,157 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Animal Crossing",
+                    cup: Some(("Crossing Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/animal_crossing.jpg"),
                 },
                 IntroReference {
                     name: "Big Blue",
+                    cup: Some(("Bell Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/big_blue.jpg"),
                 },
                 IntroReference {
                     name: "Bone Dry Dunes",
+                    cup: Some(("Special Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/bone-dry_dunes.jpg"),
                 },
                 IntroReference {
                     name: "Bowser's Castle",
+                    cup: Some(("Special Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/bowsers_castle.jpg"),
                 },
                 IntroReference {
                     name: "Cloudtop Cruise",
+                    cup: Some(("Special Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/cloudtop_cruise.jpg"),
                 },
                 IntroReference {
                     name: "Dolphin Shoals",
+                    cup: Some(("Star Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/dolphin_shoals.jpg"),
                 },
                 IntroReference {
                     name: "Dragon Driftway",
+                    cup: Some(("Egg Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/dragon_driftway.jpg"),
                 },
                 IntroReference {
                     name: "Electrodrome",
+                    cup: Some(("Star Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/electrodrome.jpg"),
                 },
                 IntroReference {
                     name: "Excitebike Arena",
+                    cup: Some(("Egg Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/excitebike_arena.jpg"),
                 },
                 IntroReference {
                     name: "Hyrule Circuit",
+                    cup: Some(("Triforce Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/hyrule_circuit.jpg"),
                 },
                 IntroReference {
                     name: "Ice Ice Outpost",
+                    cup: Some(("Triforce Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/ice_ice_outpost.jpg"),
                 },
                 IntroReference {
                     name: "Mario Circuit",
+                    cup: Some(("Flower Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/mario_circuit.jpg"),
                 },
                 IntroReference {
                     name: "Mario Kart Stadium",
+                    cup: Some(("Mushroom Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/mario_kart_stadium.jpg"),
                 },
                 IntroReference {
                     name: "Merry Mountain",
+                    cup: Some(("Moon Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/merry_mountain.jpg"),
                 },
                 IntroReference {
                     name: "Mount Wario",
+                    cup: Some(("Star Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/mount_wario.jpg"),
                 },
                 IntroReference {
                     name: "Mute City",
+                    cup: Some(("Egg Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/mute_city.jpg"),
                 },
                 IntroReference {
                     name: "Ninja Hideaway",
+                    cup: Some(("Lucky Cat Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/ninja_hideaway.jpg"),
                 },
                 IntroReference {
                     name: "Piranha Plant Cove",
+                    cup: Some(("Acorn Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/piranha_plant_cove.jpg"),
                 },
                 IntroReference {
                     name: "Rainbow Road",
+                    cup: Some(("Special Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/rainbow_road.jpg"),
                 },
                 IntroReference {
                     name: "Shy Guy Falls",
+                    cup: Some(("Flower Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/shy_guy_falls.jpg"),
                 },
                 IntroReference {
                     name: "Sky-High Sundae",
+                    cup: Some(("Propeller Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/skyhigh_sundae.jpg"),
                 },
                 IntroReference {
                     name: "Squeaky Clean Sprint",
+                    cup: Some(("Feather Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/squeaky_clean_sprint.jpg"),
                 },
                 IntroReference {
                     name: "Sunshine Airport",
+                    cup: Some(("Star Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/sunshine_airport.jpg"),
                 },
                 IntroReference {
                     name: "Super Bell Subway",
+                    cup: Some(("Bell Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/super_bell_subway.jpg"),
                 },
                 IntroReference {
                     name: "Sweet Sweet Canyon",
+                    cup: Some(("Mushroom Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/sweet_sweet_canyon.jpg"),
                 },
                 IntroReference {
                     name: "Thwomp Ruins",
+                    cup: Some(("Mushroom Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/thwomp_ruins.jpg"),
                 },
                 IntroReference {
                     name: "Toad Harbor",
+                    cup: Some(("Flower Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/toad_harbor.jpg"),
                 },
                 IntroReference {
                     name: "Twisted Mansion",
+                    cup: Some(("Flower Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/twisted_mansion.jpg"),
                 },
                 IntroReference {
                     name: "Water Park",
+                    cup: Some(("Mushroom Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/water_park.jpg"),
                 },
                 IntroReference {
                     name: "Wild Woods",
+                    cup: Some(("Crossing Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/wild_woods.jpg"),
                 },
                 IntroReference {
                     name: "Yoshi’s Island",
+                    cup: Some(("Fruit Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/yoshis_island.jpg"),
                 },
             ],
@@ -337,18 +412,22 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Bowser Castle 3 (SNES)",
+                    cup: Some(("Spiny Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/bowser_castle_3.jpg"),
                 },
                 IntroReference {
                     name: "Donut Plains 3 (SNES)",
+                    cup: Some(("Banana Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/donut_plains_3_snes.jpg"),
                 },
                 IntroReference {
                     name: "Mario Circuit 3 (SNES)",
+                    cup: Some(("Turnip Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/mario_circuit_3_snes.jpg"),
                 },
                 IntroReference {
                     name: "Rainbow Road (SNES)",
+                    cup: Some(("Triforce Cup", 1)),
                     reference: load_reference_hash!("intro/tracks/rainbow_road_snes.jpg"),
                 },
             ],
@@ -358,58 +437,72 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Amsterdam Drift (Tour)",
+                    cup: Some(("Fruit Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/amsterdam_drift_tour.jpg"),
                 },
                 IntroReference {
                     name: "Athens Dash (Tour)",
+                    cup: Some(("Feather Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/athens_dash_tour.jpg"),
                 },
                 IntroReference {
                     name: "Bangkok Rush (Tour)",
+                    cup: Some(("Boomerang Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/bangkok_rush_tour.jpg"),
                 },
                 IntroReference {
                     name: "Berlin Byways (Tour)",
+                    cup: Some(("Moon Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/berlin_byways_tour.jpg"),
                 },
                 IntroReference {
                     name: "London Loop (Tour)",
+                    cup: Some(("Rock Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/london_loop_tour.jpg"),
                 },
                 IntroReference {
                     name: "Los Angeles Laps (Tour)",
+                    cup: Some(("Cherry Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/los_angeles_laps_tour.jpg"),
                 },
                 IntroReference {
                     name: "Madrid Drive (Tour)",
+                    cup: Some(("Spiny Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/madrid_drive.jpg"),
                 },
                 IntroReference {
                     name: "New York Minute (Tour)",
+                    cup: Some(("Turnip Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/new_york_minute_tour.jpg"),
                 },
                 IntroReference {
                     name: "Paris Promenade (Tour)",
+                    cup: Some(("Golden Dash Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/paris_promenade_tour.jpg"),
                 },
                 IntroReference {
                     name: "Rome Avanti (Tour)",
+                    cup: Some(("Acorn Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/rome_avanti.jpg"),
                 },
                 IntroReference {
                     name: "Singapore Speedway (Tour)",
+                    cup: Some(("Boomerang Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/singapore_speedway_tour.jpg"),
                 },
                 IntroReference {
                     name: "Sydney Sprint (Tour)",
+                    cup: Some(("Propeller Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/sydney_sprint_tour.jpg"),
                 },
                 IntroReference {
                     name: "Tokyo Blur (Tour)",
+                    cup: Some(("Lucky Cat Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/tokyo_blur_tour.jpg"),
                 },
                 IntroReference {
                     name: "Vancouver Velocity (Tour)",
+                    cup: Some(("Cherry Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/vancouver_velocity_tour.jpg"),
                 },
             ],
@@ -419,46 +512,57 @@ lazy_static! {
             tracks: vec![
                 IntroReference {
                     name: "Coconut Mall (Wii)",
+                    cup: Some(("Golden Dash Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/coconut_mall_wii.jpg"),
                 },
                 IntroReference {
                     name: "Daisy Circuit (Wii)",
+                    cup: Some(("Acorn Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/daisy_circuit.jpg"),
                 },
                 IntroReference {
                     name: "DK's Snowboard Cross (Wii)",
+                    cup: Some(("Fruit Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/dks_snowboard_cross_wii.jpg"),
                 },
                 IntroReference {
                     name: "Grumble Volcano (Wii)",
+                    cup: Some(("Lightning Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/grumble_volcano_wii.jpg"),
                 },
                 IntroReference {
                     name: "Koopa Cape (Wii)",
+                    cup: Some(("Cherry Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/koopa_cape_wii.jpg"),
                 },
                 IntroReference {
                     name: "Maple Treeway (Wii)",
+                    cup: Some(("Rock Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/maple_treeway_wii.jpg"),
                 },
                 IntroReference {
                     name: "Moo Moo Meadows (Wii)",
+                    cup: Some(("Shell Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/moo_moo_meadows_wii.jpg"),
                 },
                 IntroReference {
                     name: "Moonview Highway (Wii)",
+                    cup: Some(("Feather Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/moonview_highway_wii.jpg"),
                 },
                 IntroReference {
                     name: "Mushroom Gorge (Wii)",
+                    cup: Some(("Propeller Cup", 2)),
                     reference: load_reference_hash!("intro/tracks/mushroom_gorge_wii.jpg"),
                 },
                 IntroReference {
                     name: "Rainbow Road (Wii)",
+                    cup: Some(("Spiny Cup", 3)),
                     reference: load_reference_hash!("intro/tracks/rainbow_road_wii.jpg"),
                 },
                 IntroReference {
                     name: "Wario’s Gold Mine (Wii)",
+                    cup: Some(("Triforce Cup", 0)),
                     reference: load_reference_hash!("intro/tracks/warios_gold_mine_wii.jpg"),
                 },
             ],
@@ -466,6 +570,11 @@ lazy_static! {
     ];
 }
 
+pub(crate) fn load_references() {
+    lazy_static::initialize(&REFERENCE_HASH);
+    lazy_static::initialize(&VARIANT_GROUPS);
+}
+
 #[derive(Debug, PartialEq, Serialize, Clone, Copy)]
 pub struct Intro {
     #[serde(rename = "course_name")]
@@ -473,20 +582,15 @@ pub struct Intro {
 }
 
 impl Reference for Intro {
-    fn compare(frame: &image::DynamicImage) -> bool {
-        let crop = frame.crop_imm(111, 589, 44, 37);
-
-        let check_hash = hasher::hash_image(crop);
-        let delta = REFERENCE_HASH.dist(&check_hash);
-
-        if delta > 3 {
+    fn compare(frame: &Frame) -> bool {
+        if distance(frame) > threshold("intro") {
             return false;
         }
 
         return check_speed_slice(frame);
     }
 
-    fn process(frame: &image::DynamicImage) -> Option<Screen> {
+    fn process(frame: &Frame) -> Option<Screen> {
         let variant = get_variant_image(&frame);
         let track = get_track_image(&frame);
 
@@ -514,6 +618,31 @@ impl Reference for Intro {
 
         Some(Screen::Intro(Intro { course }))
     }
+
+    fn regions() -> Vec<[u32; 4]> {
+        vec![LOGO_CROP, VARIANT_CROP, TRACK_CROP]
+    }
+}
+
+// the badge beside the course name, the same on every intro
+const LOGO_CROP: [u32; 4] = [111, 589, 44, 37];
+
+fn distance(frame: &image::DynamicImage) -> u32 {
+    let [x, y, width, height] = LOGO_CROP;
+    let crop = frame.crop_imm(x, y, width, height);
+
+    let check_hash = hasher::hash_image(crop);
+    REFERENCE_HASH.dist(&check_hash)
+}
+
+pub(crate) fn sample(frame: &image::DynamicImage) -> Sample {
+    Sample {
+        reference: "intro".into(),
+        region: LOGO_CROP,
+        player: None,
+        label: None,
+        distance: distance(frame),
+    }
 }
 
 fn check_speed_slice(frame: &image::DynamicImage) -> bool {
@@ -526,16 +655,24 @@ fn check_speed_slice(frame: &image::DynamicImage) -> bool {
     return r > 60_000 && g > 60_000 && b > 60_000;
 }
 
+// the cup's logo, and the course name next to it, in the bottom left
+pub(crate) const VARIANT_CROP: [u32; 4] = [258, 638, 80, 18];
+pub(crate) const TRACK_CROP: [u32; 4] = [338, 620, 350, 36];
+// the text is white, so anything lighter than this is kept
+pub(crate) const TEXT_CUTOFF: u16 = 220;
+
 pub fn get_variant_image(image: &image::DynamicImage) -> image::DynamicImage {
-    let mut variant = image.crop_imm(258, 638, 80, 18);
-    max_color_diff(&mut variant, 220);
+    let [x, y, width, height] = VARIANT_CROP;
+    let mut variant = image.crop_imm(x, y, width, height);
+    max_color_diff(&mut variant, TEXT_CUTOFF);
 
     return variant;
 }
 
 pub fn get_track_image(image: &image::DynamicImage) -> image::DynamicImage {
-    let mut track = image.crop_imm(338, 620, 350, 36);
-    max_color_diff(&mut track, 220);
+    let [x, y, width, height] = TRACK_CROP;
+    let mut track = image.crop_imm(x, y, width, height);
+    max_color_diff(&mut track, TEXT_CUTOFF);
 
     return track;
 }
@@ -576,6 +713,7 @@ fn find_closest_track(
 #[cfg(test)]
 mod tests {
     use super::Intro;
+    use crate::frame::Frame;
     use crate::reference::Reference;
 
     macro_rules! test_frame {
@@ -593,7 +731,7 @@ mod tests {
     #[test]
     fn ignores_before_title() {
         let frame = test_frame!("no_track_name");
-        let result = Intro::compare(&frame);
+        let result = Intro::compare(&Frame::new(&frame));
 
         assert!(!result)
     }
@@ -601,7 +739,7 @@ mod tests {
     #[test]
     fn ignores_partial_title() {
         let frame = test_frame!("partial_track_name");
-        let result = Intro::compare(&frame);
+        let result = Intro::compare(&Frame::new(&frame));
 
         assert!(!result)
     }
@@ -609,7 +747,7 @@ mod tests {
     #[test]
     fn approves_full_title() {
         let frame = test_frame!("full_track_name");
-        let result = Intro::compare(&frame);
+        let result = Intro::compare(&Frame::new(&frame));
 
         assert!(result)
     }
@@ -1,7 +1,9 @@
 use super::Screen;
+use crate::frame::Frame;
 use crate::hasher;
 use crate::load_reference_hash;
 use crate::reference::Reference;
+use crate::thresholds::{threshold, Sample};
 use lazy_static::lazy_static;
 use serde::Serialize;
 
@@ -10,18 +12,42 @@ lazy_static! {
       load_reference_hash!("loading/loading_reference.jpg");
 }
 
+pub(crate) fn load_references() {
+    lazy_static::initialize(&REFERENCE_HASH);
+}
+
 #[derive(Debug, PartialEq, Serialize, Clone, Copy)]
 pub struct Loading {}
 
 impl Reference for Loading {
-    fn process(_frame: &image::DynamicImage) -> Option<Screen> {
+    fn process(_frame: &Frame) -> Option<Screen> {
         Some(Screen::Loading(Loading {}))
     }
 
-    fn compare(frame: &image::DynamicImage) -> bool {
-        let crop = frame.crop_imm(670, 20, 100, 100);
-        let check_hash = hasher::hash_image(crop);
-        let delta = REFERENCE_HASH.dist(&check_hash);
-        return delta < 5;
+    fn compare(frame: &Frame) -> bool {
+        distance(frame) <= threshold("loading")
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        vec![CROP]
+    }
+}
+
+pub(crate) const CROP: [u32; 4] = [670, 20, 100, 100];
+
+fn distance(frame: &image::DynamicImage) -> u32 {
+    let [x, y, width, height] = CROP;
+    let crop = frame.crop_imm(x, y, width, height);
+    let check_hash = hasher::hash_image(crop);
+    REFERENCE_HASH.dist(&check_hash)
+}
+
+pub(crate) fn sample(frame: &image::DynamicImage) -> Sample {
+    Sample {
+        reference: "loading".into(),
+        region: CROP,
+        player: None,
+        label: None,
+        distance: distance(frame),
     }
 }
@@ -1,7 +1,9 @@
 use super::super::hasher;
 use super::super::reference::Reference;
 use super::Screen;
+use crate::frame::Frame;
 use crate::load_reference_hash;
+use crate::thresholds::{threshold, Sample};
 use lazy_static::lazy_static;
 use serde::Serialize;
 
@@ -9,20 +11,43 @@ lazy_static! {
     static ref REFERENCE_HASH: image_hasher::ImageHash = load_reference_hash!("main_menu.jpg");
 }
 
+pub(crate) fn load_references() {
+    lazy_static::initialize(&REFERENCE_HASH);
+}
+
 #[derive(Debug, PartialEq, Serialize, Clone, Copy)]
 pub struct MainMenu {}
 
 impl Reference for MainMenu {
-    fn process(_frame: &image::DynamicImage) -> Option<Screen> {
+    fn process(_frame: &Frame) -> Option<Screen> {
         Some(Screen::MainMenu(MainMenu {}))
     }
 
-    fn compare(frame: &image::DynamicImage) -> bool {
-        let crop = frame.crop_imm(220, 467, 122, 24);
+    fn compare(frame: &Frame) -> bool {
+        distance(frame) <= threshold("main_menu")
+    }
 
-        let check_hash = hasher::hash_image(crop);
-        let delta = REFERENCE_HASH.dist(&check_hash);
+    fn regions() -> Vec<[u32; 4]> {
+        vec![CROP]
+    }
+}
+
+const CROP: [u32; 4] = [220, 467, 122, 24];
+
+fn distance(frame: &image::DynamicImage) -> u32 {
+    let [x, y, width, height] = CROP;
+    let crop = frame.crop_imm(x, y, width, height);
+
+    let check_hash = hasher::hash_image(crop);
+    REFERENCE_HASH.dist(&check_hash)
+}
 
-        return delta <= 10;
+pub(crate) fn sample(frame: &image::DynamicImage) -> Sample {
+    Sample {
+        reference: "main_menu".into(),
+        region: CROP,
+        player: None,
+        label: None,
+        distance: distance(frame),
     }
 }
@@ -1,9 +1,13 @@
 use super::Screen;
-use crate::color::average_colors;
-use crate::color::max_color_diff;
+use crate::characters::find_character;
+use crate::color::{light_text_cutoff, max_color_diff};
+use crate::digits::read_fixed_number;
+use crate::frame::Frame;
 use crate::hasher;
 use crate::load_reference_hash;
+use crate::ocr::read_text;
 use crate::reference::Reference;
+use crate::thresholds::{threshold, Sample};
 use lazy_static::lazy_static;
 use rayon::prelude::*;
 use serde::{ser::SerializeMap, Serialize, Serializer};
@@ -15,6 +19,11 @@ lazy_static! {
         load_reference_hash!("match_result/200.jpg");
 }
 
+pub(crate) fn load_references() {
+    lazy_static::initialize(&REFERENCE_HASH_150);
+    lazy_static::initialize(&REFERENCE_HASH_200);
+}
+
 #[derive(Debug, PartialEq, Serialize, Clone)]
 pub struct PlayerResult {
     #[serde(skip_serializing)]
@@ -24,6 +33,9 @@ pub struct PlayerResult {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     score: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 }
 
 fn player_result_vec_serializer<S: Serializer>(
@@ -33,39 +45,69 @@ fn player_result_vec_serializer<S: Serializer>(
     let mut map = serializer.serialize_map(Some(player_results.len()))?;
 
     for player in player_results.iter() {
-        let name = match player.index {
-            0 => "player_one",
-            1 => "player_two",
-            2 => "player_three",
-            3 => "player_four",
-            _ => panic!("too many players! only four supported!"),
-        };
-
+        let name = super::player_name(player.index);
         map.serialize_entry(name, player)?;
     }
 
     map.end()
 }
 
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct Racer {
+    position: u8,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<u8>,
+
+    is_human: bool,
+
+    #[serde(
+        serialize_with = "super::serialize_player",
+        skip_serializing_if = "Option::is_none"
+    )]
+    player: Option<u8>,
+
+    /// The name shown on the row - the Mii name for players, or the
+    /// character's name for CPUs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    /// The character on the row's icon, if it's one we have a reference for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    character: Option<&'static str>,
+}
+
 #[derive(Debug, PartialEq, Serialize, Clone)]
 pub struct MatchResult {
     #[serde(serialize_with = "player_result_vec_serializer", flatten)]
     players: Vec<PlayerResult>,
     speed: Option<u8>,
+
+    /// Every row on the scoreboard, CPUs included, in finishing order
+    racers: Vec<Racer>,
 }
 
 const POSITION_HEIGHT: u32 = 38;
 const POSITION_MARGIN: u32 = 4;
 const COLOR_STRIP_HEIGHT: usize = 3;
 const COLOR_THRESHOLD: usize = 55_000;
+const ICON_CROP: [u32; 2] = [110, 2];
+const ICON_SIZE: u32 = 34;
+const NAME_CROP: [u32; 2] = [150, 0];
+const NAME_WIDTH: u32 = 330;
+
+// the size of a single scoreboard digit
+const DIGIT_WIDTH: u32 = 19;
+const DIGIT_HEIGHT: u32 = POSITION_HEIGHT;
+const SCORE_OFFSETS: [u32; 2] = [0, 23];
 
 impl Reference for MatchResult {
-    fn compare(frame: &image::DynamicImage) -> bool {
+    fn compare(frame: &Frame) -> bool {
         race_speed(frame).is_some()
     }
 
-    fn process(frame: &image::DynamicImage) -> Option<Screen> {
-        let mut players = (0..12)
+    fn process(frame: &Frame) -> Option<Screen> {
+        let mut racers = (0..12)
             .par_bridge()
             .filter_map(|i| {
                 let offset = i * (POSITION_HEIGHT + POSITION_MARGIN);
@@ -82,25 +124,65 @@ impl Reference for MatchResult {
                 let average_blue = totals[2] / COLOR_STRIP_HEIGHT;
 
                 let player = if average_red > COLOR_THRESHOLD && average_green > COLOR_THRESHOLD {
-                    0
+                    Some(0)
                 } else if average_green > COLOR_THRESHOLD && average_blue > COLOR_THRESHOLD {
-                    1
+                    Some(1)
                 } else if average_green > COLOR_THRESHOLD {
-                    3
+                    Some(3)
                 } else if average_red > COLOR_THRESHOLD {
-                    2
+                    Some(2)
                 } else {
-                    return None;
+                    None
                 };
 
-                let mut score_section = frame.crop_imm(543, 132 + offset, 45, POSITION_HEIGHT);
+                let score_section = frame.crop_imm(543, 132 + offset, 45, POSITION_HEIGHT);
+
+                // players' scores are dark on their colour, and CPUs' are
+                // white on a see-through row. Empty rows won't have a score
+                // to read either way.
+                let score = calculate_score(&score_section, player.is_none());
+                if player.is_none() && score.is_none() {
+                    return None;
+                }
+
+                let [icon_x, icon_y] = ICON_CROP;
+                let icon = frame.crop_imm(icon_x, 132 + offset + icon_y, ICON_SIZE, ICON_SIZE);
 
-                let score = calculate_score(&mut score_section);
+                let [name_x, name_y] = NAME_CROP;
+                let name_section =
+                    frame.crop_imm(name_x, 132 + offset + name_y, NAME_WIDTH, POSITION_HEIGHT);
 
-                Some(PlayerResult {
-                    index: player,
+                Some(Racer {
                     position: (i as u8) + 1,
                     score,
+                    is_human: player.is_some(),
+                    player,
+                    name: read_text(&name_section, player.is_some()),
+                    character: find_character(&icon),
+                })
+            })
+            .collect::<Vec<Racer>>();
+
+        racers.sort_unstable_by_key(|racer| racer.position);
+
+        // the table is sorted by score, so a CPU score that's higher than the
+        // row above it was misread
+        let mut previous: Option<u8> = None;
+        for racer in racers.iter_mut() {
+            if !racer.is_human && racer.score > previous && previous.is_some() {
+                racer.score = None;
+            }
+            previous = racer.score.or(previous);
+        }
+
+        let mut players = racers
+            .iter()
+            .filter_map(|racer| {
+                racer.player.map(|index| PlayerResult {
+                    index,
+                    position: racer.position,
+                    score: racer.score,
+                    name: racer.name.clone(),
                 })
             })
             .collect::<Vec<PlayerResult>>();
@@ -114,68 +196,157 @@ impl Reference for MatchResult {
         let result = MatchResult {
             players,
             speed: race_speed(frame),
+            racers,
         };
         return Some(Screen::MatchResult(result));
     }
+
+    fn regions() -> Vec<[u32; 4]> {
+        let rows = (0..12).flat_map(|i| {
+            let top = 132 + i * (POSITION_HEIGHT + POSITION_MARGIN);
+            let [icon_x, icon_y] = ICON_CROP;
+            let [name_x, name_y] = NAME_CROP;
+
+            [
+                [icon_x, top + icon_y, ICON_SIZE, ICON_SIZE],
+                [name_x, top + name_y, NAME_WIDTH, POSITION_HEIGHT],
+                [543, top, 45, POSITION_HEIGHT],
+            ]
+        });
+
+        std::iter::once(SPEED_CROP).chain(rows).collect()
+    }
 }
 
+// the 150cc/200cc badge in the top left
+pub(crate) const SPEED_CROP: [u32; 4] = [37, 28, 99, 26];
+
 fn race_speed(frame: &image::DynamicImage) -> Option<u8> {
-    let crop = frame.crop_imm(37, 28, 99, 26);
+    speed_distances(frame)
+        .into_iter()
+        .find(|(_, distance)| *distance <= threshold("match_result/speed"))
+        .map(|(speed, _)| speed)
+}
+
+/// How close the badge is to each speed's reference, 200cc first
+fn speed_distances(frame: &image::DynamicImage) -> [(u8, u32); 2] {
+    let [x, y, width, height] = SPEED_CROP;
+    let crop = frame.crop_imm(x, y, width, height);
 
     let check_hash = hasher::hash_image(crop);
 
-    if REFERENCE_HASH_200.dist(&check_hash) < 10 {
-        Some(200)
-    } else if REFERENCE_HASH_150.dist(&check_hash) < 10 {
-        Some(150)
+    [
+        (200, REFERENCE_HASH_200.dist(&check_hash)),
+        (150, REFERENCE_HASH_150.dist(&check_hash)),
+    ]
+}
+
+pub(crate) fn samples(frame: &image::DynamicImage) -> Vec<Sample> {
+    speed_distances(frame)
+        .into_iter()
+        .map(|(speed, distance)| Sample {
+            reference: "match_result/speed".into(),
+            region: SPEED_CROP,
+            player: None,
+            label: Some(speed.to_string()),
+            distance,
+        })
+        .collect()
+}
+
+fn calculate_score(section: &image::DynamicImage, light_text: bool) -> Option<u8> {
+    let mut section = section.clone();
+    if light_text {
+        let cutoff = light_text_cutoff(&section);
+        max_color_diff(&mut section, cutoff);
+        section.invert();
     } else {
-        None
+        max_color_diff(&mut section, 130);
     }
+
+    read_fixed_number(&section, &SCORE_OFFSETS, DIGIT_WIDTH, DIGIT_HEIGHT)
+        .and_then(|score| u8::try_from(score.value).ok())
 }
 
-fn calculate_score(section: &mut image::DynamicImage) -> Option<u8> {
-    max_color_diff(section, 130);
+#[cfg(test)]
+mod tests {
+    use super::MatchResult;
+    use crate::frame::Frame;
+    use crate::reference::Reference;
+    use crate::screens::Screen;
+    use pretty_assertions::assert_eq;
 
-    let tens = get_number(&section, 0);
-    let ones = get_number(&section, 23);
+    #[test]
+    fn reads_every_row() {
+        let image_data = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec-data/screens/match_result/every_row.jpg"
+        ));
+        let frame = image::load_from_memory(image_data).expect("failed to open image");
+        let Some(Screen::MatchResult(result)) = MatchResult::process(&Frame::new(&frame)) else {
+            panic!("didn't read the match results");
+        };
 
-    tens.zip(ones).map(|(t, o)| (t * 10) + o)
-}
+        let rows: Vec<_> = result
+            .racers
+            .iter()
+            .map(|racer| (racer.position, racer.score, racer.player, racer.character))
+            .collect();
 
-fn get_number(section: &image::DynamicImage, x_offset: u32) -> Option<u8> {
-    let top = is_black(&section.crop_imm(x_offset + 7, 6, 3, 1));
-    let top_left = is_black(&section.crop_imm(x_offset + 2, 10, 1, 3));
-    let top_right = is_black(&section.crop_imm(x_offset + 16, 10, 1, 3));
-    let center = is_black(&section.crop_imm(x_offset + 7, 18, 3, 1));
-    let bottom_left = is_black(&section.crop_imm(x_offset + 2, 22, 3, 1));
-    let bottom_right = is_black(&section.crop_imm(x_offset + 16, 22, 3, 1));
-    let bottom = is_black(&section.crop_imm(x_offset + 7, 30, 3, 1));
-
-    match (
-        top,
-        top_left,
-        top_right,
-        center,
-        bottom_left,
-        bottom_right,
-        bottom,
-    ) {
-        (false, false, true, false, false, true, false) => Some(1),
-        (true, false, true, true, true, false, true) => Some(2),
-        (true, false, true, true, false, true, true) => Some(3),
-        (false, true, true, true, false, true, false) => Some(4),
-        (true, true, false, true, false, true, true) => Some(5),
-        (true, true, false, true, true, true, true) => Some(6),
-        (true, false, true, false, false, true, false) => Some(7),
-        (true, true, true, true, true, true, true) => Some(8),
-        (true, true, true, true, false, true, true) => Some(9),
-        _ => None,
+        assert_eq!(
+            rows,
+            vec![
+                (1, Some(77), Some(3), Some("Birdo (Light Blue)")),
+                (2, Some(76), Some(1), Some("Roy")),
+                (3, Some(55), Some(0), Some("Baby Luigi")),
+                (4, Some(52), None, Some("Baby Peach")),
+                (5, Some(44), None, Some("Donkey Kong")),
+                (6, Some(43), Some(2), Some("Koopa Troopa")),
+                (7, Some(41), None, Some("Inkling Girl")),
+                (8, Some(35), None, Some("Pink Gold Peach")),
+                (9, Some(23), None, Some("Wendy")),
+                (10, Some(22), None, Some("Luigi")),
+                (11, Some(14), None, Some("Baby Rosalina")),
+                (12, Some(10), None, Some("Wario")),
+            ]
+        );
     }
-}
 
-fn is_black(crop: &image::DynamicImage) -> bool {
-    let [r, g, b] = average_colors(crop);
-    let average = (r as u32 + g as u32 + b as u32) / 3;
+    #[test]
+    fn reads_names() {
+        // a later frame of the same results than `every_row`, which the glyph
+        // references were cut from
+        let image_data = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec-data/screens/match_result/names.jpg"
+        ));
+        let frame = image::load_from_memory(image_data).expect("failed to open image");
+        let Some(Screen::MatchResult(result)) = MatchResult::process(&Frame::new(&frame)) else {
+            panic!("didn't read the match results");
+        };
+
+        let names: Vec<_> = result
+            .racers
+            .iter()
+            .map(|racer| racer.name.as_deref())
+            .collect();
 
-    return average < 25_000;
+        assert_eq!(
+            names,
+            vec![
+                Some("Birdo (Light Blue)"),
+                Some("Roy"),
+                Some("Baby Luigi"),
+                Some("Baby Peach"),
+                Some("Donkey Kong"),
+                Some("Koopa Troopa"),
+                Some("Inkling Girl"),
+                Some("Pink Gold Peach"),
+                Some("Wendy"),
+                Some("Luigi"),
+                Some("Baby Rosalina"),
+                Some("Wario"),
+            ]
+        );
+    }
 }
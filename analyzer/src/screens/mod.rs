@@ -1,23 +1,42 @@
+use crate::reference::Reference;
 use serde::Serialize;
 
+pub mod course_vote;
+pub mod highlights;
 pub mod intro;
 pub mod loading;
 pub mod main_menu;
 pub mod match_result;
+pub mod online_lobby;
+pub mod pause;
 pub mod race;
 pub mod race_result;
+pub mod race_rules;
+pub mod replay;
 pub mod select_character;
+pub mod select_course;
+pub mod time_trial;
+pub mod time_trial_result;
 
 #[derive(Debug, PartialEq, Serialize, Clone)]
 #[serde(untagged)]
 pub enum Screen {
+    CourseVote(course_vote::CourseVote),
+    Highlights(highlights::Highlights),
     Intro(intro::Intro),
     Loading(loading::Loading),
     MainMenu(main_menu::MainMenu),
     MatchResult(match_result::MatchResult),
+    OnlineLobby(online_lobby::OnlineLobby),
+    Pause(pause::Pause),
     Race(race::Race),
+    RaceRules(race_rules::RaceRules),
     RaceResult(race_result::RaceResult),
+    Replay(replay::Replay),
     SelectCharacter(select_character::SelectCharacter),
+    SelectCourse(select_course::SelectCourse),
+    TimeTrial(time_trial::TimeTrial),
+    TimeTrialResult(time_trial_result::TimeTrialResult),
 
     Unknown,
 }
@@ -25,16 +44,79 @@ pub enum Screen {
 impl Screen {
     pub fn event_type(&self) -> &'static str {
         match self {
+            Screen::CourseVote(_) => "course_vote_screen",
+            Screen::Highlights(_) => "highlights_screen",
             Screen::Intro(_) => "intro_screen",
             Screen::Loading(_) => "loading_screen",
             Screen::MainMenu(_) => "main_menu_screen",
+            Screen::Pause(_) => "pause_screen",
             Screen::Race(_) => "race_screen",
+            Screen::RaceRules(_) => "race_rules_screen",
+            Screen::Replay(_) => "replay_screen",
             Screen::SelectCharacter(_) => "select_character_screen",
+            Screen::SelectCourse(_) => "select_course_screen",
             Screen::MatchResult(_) => "match_result_screen",
+            Screen::OnlineLobby(_) => "online_lobby_screen",
             Screen::RaceResult(_) => "race_result_screen",
+            Screen::TimeTrial(_) => "time_trial_screen",
+            Screen::TimeTrialResult(_) => "time_trial_result_screen",
 
             // Skip Unknown screens - no need to emit
             Screen::Unknown => "unknown_screen",
         }
     }
+
+    /// The parts of the frame this screen was read from. See
+    /// `Reference::regions`
+    pub fn regions(&self) -> Vec<[u32; 4]> {
+        match self {
+            Screen::CourseVote(_) => course_vote::CourseVote::regions(),
+            Screen::Highlights(_) => highlights::Highlights::regions(),
+            Screen::Intro(_) => intro::Intro::regions(),
+            Screen::Loading(_) => loading::Loading::regions(),
+            Screen::MainMenu(_) => main_menu::MainMenu::regions(),
+            Screen::Pause(_) => pause::Pause::regions(),
+            Screen::Race(_) => race::Race::regions(),
+            Screen::RaceRules(_) => race_rules::RaceRules::regions(),
+            Screen::Replay(_) => replay::Replay::regions(),
+            Screen::SelectCharacter(_) => select_character::SelectCharacter::regions(),
+            Screen::SelectCourse(_) => select_course::SelectCourse::regions(),
+            Screen::MatchResult(_) => match_result::MatchResult::regions(),
+            Screen::OnlineLobby(_) => online_lobby::OnlineLobby::regions(),
+            Screen::RaceResult(_) => race_result::RaceResult::regions(),
+            Screen::TimeTrial(_) => time_trial::TimeTrial::regions(),
+            Screen::TimeTrialResult(_) => time_trial_result::TimeTrialResult::regions(),
+            Screen::Unknown => vec![],
+        }
+    }
+}
+
+/// Players are keyed by their controller slot when serialized
+pub(crate) fn player_name(index: u8) -> &'static str {
+    match index {
+        0 => "player_one",
+        1 => "player_two",
+        2 => "player_three",
+        3 => "player_four",
+        _ => panic!("too many players! only four supported!"),
+    }
+}
+
+/// Serializes a player's index as their name
+pub(crate) fn serialize_index<S: serde::Serializer>(
+    index: &u8,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(player_name(*index))
+}
+
+/// Like `player_name`, for rows that may or may not belong to a player
+pub(crate) fn serialize_player<S: serde::Serializer>(
+    index: &Option<u8>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match index {
+        Some(index) => serializer.serialize_str(player_name(*index)),
+        None => serializer.serialize_none(),
+    }
 }
@@ -0,0 +1,132 @@
+use super::race_result::{read_digits, read_row};
+use super::Screen;
+use crate::color::{average_colors, player_color};
+use crate::frame::Frame;
+use crate::ocr::read_text;
+use crate::reference::Reference;
+use serde::Serialize;
+
+// While waiting for an online race, everyone in the room is listed down the
+// right of the screen with their VR (versus rating) next to their name. The
+// players on this console have rows in their colours.
+//
+// There's no frame of the lobby in the corpus, so none of these have been
+// measured. The rows are taken to be the same size as the rows of the results
+// table, starting from the middle of the screen. Until there's a frame to
+// check them against, the lobby isn't in `SCREENS`.
+const ROW_ORIGIN: [u32; 2] = [640, 70];
+const ROW_HEIGHT: u32 = 48;
+const ROW_MARGIN: u32 = 4;
+const ROWS: u32 = 12;
+const COLOR_CROP: [u32; 2] = [4, 2];
+const NAME_CROP: [u32; 2] = [60, 7];
+const NAME_WIDTH: u32 = 260;
+const VR_CROP: [u32; 2] = [470, 7];
+const VR_WIDTH: u32 = 120;
+const TEXT_HEIGHT: u32 = 34;
+// a couple of rows need a rating before we believe this is the lobby. a
+// rating is never shorter than this, other than for brand new players
+const MIN_RATED: usize = 2;
+const MIN_VR: u32 = 100;
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct OnlineLobby {
+    pub racers: Vec<LobbyRacer>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct LobbyRacer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(
+        serialize_with = "super::serialize_player",
+        skip_serializing_if = "Option::is_none"
+    )]
+    player: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vr: Option<u32>,
+}
+
+impl Reference for OnlineLobby {
+    fn compare(frame: &Frame) -> bool {
+        if frame.is_splitscreen() {
+            return false;
+        }
+
+        (0..ROWS)
+            .filter(|i| {
+                let player = row_player(frame, row_top(*i));
+                read_vr(frame, row_top(*i), player.is_some()).is_some_and(|vr| vr >= MIN_VR)
+            })
+            .take(MIN_RATED)
+            .count()
+            == MIN_RATED
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let racers: Vec<LobbyRacer> = (0..ROWS)
+            .filter_map(|i| {
+                let top = row_top(i);
+                let player = row_player(frame, top);
+
+                let [x, y] = NAME_CROP;
+                let name = frame.crop_imm(ROW_ORIGIN[0] + x, top + y, NAME_WIDTH, TEXT_HEIGHT);
+                let name = read_text(&name, player.is_some());
+                let vr = read_vr(frame, top, player.is_some());
+
+                // rows fill from the top as people join
+                if name.is_none() && vr.is_none() {
+                    return None;
+                }
+
+                Some(LobbyRacer { name, player, vr })
+            })
+            .collect();
+
+        if racers.is_empty() {
+            return None;
+        }
+
+        Some(Screen::OnlineLobby(OnlineLobby { racers }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        (0..ROWS)
+            .flat_map(|i| {
+                let top = row_top(i);
+                let row =
+                    |[x, y]: [u32; 2], width, height| [ROW_ORIGIN[0] + x, top + y, width, height];
+
+                [
+                    row(COLOR_CROP, 40, 4),
+                    row(NAME_CROP, NAME_WIDTH, TEXT_HEIGHT),
+                    row(VR_CROP, VR_WIDTH, TEXT_HEIGHT),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn row_top(i: u32) -> u32 {
+    ROW_ORIGIN[1] + i * (ROW_HEIGHT + ROW_MARGIN)
+}
+
+fn row_player(frame: &Frame, top: u32) -> Option<u8> {
+    let [x, y] = COLOR_CROP;
+
+    player_color(average_colors(&*frame.view(
+        ROW_ORIGIN[0] + x,
+        top + y,
+        40,
+        4,
+    )))
+}
+
+fn read_vr(frame: &image::DynamicImage, top: u32, dark_text: bool) -> Option<u32> {
+    let [x, y] = VR_CROP;
+    let section = frame.crop_imm(ROW_ORIGIN[0] + x, top + y, VR_WIDTH, TEXT_HEIGHT);
+
+    read_row(&section, dark_text, read_digits)
+}
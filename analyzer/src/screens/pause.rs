@@ -0,0 +1,60 @@
+use super::Screen;
+use crate::color::{average_colors, get_overall_average, mostly_yellow};
+use crate::frame::Frame;
+use crate::reference::Reference;
+use serde::Serialize;
+
+// The pause menu is drawn in the middle of the (dimmed) screen, regardless of
+// how many players there are. The selected option gets a yellow highlight.
+const OPTION_CROP: [(PauseOption, [u32; 4]); 3] = [
+    (PauseOption::Continue, [500, 292, 280, 6]),
+    (PauseOption::Restart, [500, 352, 280, 6]),
+    (PauseOption::Quit, [500, 412, 280, 6]),
+];
+const DIMMED_CROP: [[u32; 2]; 4] = [[8, 8], [1232, 8], [8, 672], [1232, 672]];
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PauseOption {
+    Continue,
+    Restart,
+    Quit,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct Pause {
+    pub selected: PauseOption,
+}
+
+impl Reference for Pause {
+    fn compare(frame: &Frame) -> bool {
+        let dimmed = DIMMED_CROP
+            .iter()
+            .all(|[x, y]| get_overall_average(&*frame.view(*x, *y, 40, 40)) < 12_000);
+
+        dimmed && get_selected(frame).is_some()
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let selected = get_selected(frame)?;
+
+        Some(Screen::Pause(Pause { selected }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        OPTION_CROP.iter().map(|(_, crop)| *crop).collect()
+    }
+}
+
+fn get_selected(frame: &Frame) -> Option<PauseOption> {
+    let mut highlighted = OPTION_CROP.iter().filter(|(_, [x, y, width, height])| {
+        mostly_yellow(average_colors(&*frame.view(*x, *y, *width, *height)))
+    });
+
+    // only one option can be highlighted at a time - anything else isn't the
+    // pause menu
+    match (highlighted.next(), highlighted.next()) {
+        (Some((option, _)), None) => Some(*option),
+        _ => None,
+    }
+}
@@ -1,15 +1,21 @@
 use super::Screen;
-use crate::color::{average_colors, COLOR_THRESHOLD};
+use crate::color::{average_colors, get_overall_average, COLOR_THRESHOLD};
+use crate::frame::Frame;
 use crate::hasher;
 use crate::load_reference_hash;
 use crate::reference::Reference;
-use crate::util::is_splitscreen;
+use crate::thresholds::{threshold, Sample};
 use lazy_static::lazy_static;
 use rayon::prelude::*;
 use serde::{ser::SerializeMap, Serialize, Serializer};
 use std::fmt::Display;
 use std::vec::Vec;
 
+pub mod alerts;
+pub mod events;
+#[cfg(feature = "minimap")]
+pub mod minimap;
+
 #[derive(Debug)]
 struct ImageReference {
     files: Vec<image_hasher::ImageHash>,
@@ -49,10 +55,20 @@ pub enum Item {
     Star,
 }
 
+impl Item {
+    /// The item's name, as it's serialized
+    pub fn name(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => unreachable!("items serialize to strings"),
+        }
+    }
+}
+
 struct ItemReference {
     files: Vec<image_hasher::ImageHash>,
     item: Item,
-    threshold: u8,
+    threshold: u32,
 }
 
 lazy_static! {
@@ -135,7 +151,7 @@ lazy_static! {
                 load_reference_hash!("items/banana-double_2.jpg"),
                 load_reference_hash!("items/banana-double_3.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/banana-double"),
         },
         ItemReference {
             item: Item::BananaTriple,
@@ -145,7 +161,7 @@ lazy_static! {
                 load_reference_hash!("items/banana-triple_2.jpg"),
                 load_reference_hash!("items/banana-triple_3.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/banana-triple"),
         },
         ItemReference {
             item: Item::Banana,
@@ -157,7 +173,7 @@ lazy_static! {
                 load_reference_hash!("items/banana_4.jpg"),
                 load_reference_hash!("items/banana_5.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/banana"),
         },
         ItemReference {
             item: Item::BlueShell,
@@ -165,7 +181,7 @@ lazy_static! {
                 load_reference_hash!("items/blue-shell.jpg"),
                 load_reference_hash!("items/blue-shell_1.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/blue-shell"),
         },
         ItemReference {
             item: Item::Bomb,
@@ -174,7 +190,7 @@ lazy_static! {
                 load_reference_hash!("items/bomb_1.jpg"),
                 load_reference_hash!("items/bomb_2.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/bomb"),
         },
         ItemReference {
             item: Item::Boomerang,
@@ -185,7 +201,7 @@ lazy_static! {
                 load_reference_hash!("items/boomerang_3.jpg"),
                 load_reference_hash!("items/boomerang_4.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/boomerang"),
         },
         ItemReference {
             item: Item::Bullet,
@@ -196,7 +212,7 @@ lazy_static! {
                 load_reference_hash!("items/bullet_3.jpg"),
                 load_reference_hash!("items/bullet_4.jpg"),
             ],
-            threshold: 15,
+            threshold: threshold("items/bullet"),
         },
         ItemReference {
             item: Item::Coin,
@@ -205,12 +221,12 @@ lazy_static! {
                 load_reference_hash!("items/coin_1.jpg"),
                 load_reference_hash!("items/coin_3.jpg"),
             ],
-            threshold: 15,
+            threshold: threshold("items/coin"),
         },
         ItemReference {
             item: Item::CrazyEight,
             files: vec![load_reference_hash!("items/crazy-eight_meh.jpg"),],
-            threshold: 16,
+            threshold: threshold("items/crazy-eight"),
         },
         ItemReference {
             item: Item::FireFlower,
@@ -221,7 +237,7 @@ lazy_static! {
                 load_reference_hash!("items/fire-flower_3.jpg"),
                 load_reference_hash!("items/fire-flower_use.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/fire-flower"),
         },
         ItemReference {
             item: Item::Ghost,
@@ -229,7 +245,7 @@ lazy_static! {
                 load_reference_hash!("items/ghost.jpg"),
                 load_reference_hash!("items/ghost_1.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/ghost"),
         },
         ItemReference {
             item: Item::GoldenMushroom,
@@ -241,7 +257,7 @@ lazy_static! {
                 load_reference_hash!("items/golden-mushroom_4.jpg"),
                 load_reference_hash!("items/golden-mushroom_5.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/golden-mushroom"),
         },
         ItemReference {
             item: Item::GreenShellDouble,
@@ -251,7 +267,7 @@ lazy_static! {
                 load_reference_hash!("items/green-shell-double_2.jpg"),
                 load_reference_hash!("items/green-shell-double_3.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/green-shell-double"),
         },
         ItemReference {
             item: Item::GreenShellTriple,
@@ -263,7 +279,7 @@ lazy_static! {
                 load_reference_hash!("items/green-shell-triple_4.jpg"),
                 load_reference_hash!("items/green-shell-triple_5.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/green-shell-triple"),
         },
         ItemReference {
             item: Item::GreenShell,
@@ -275,7 +291,7 @@ lazy_static! {
                 load_reference_hash!("items/green-shell_4.jpg"),
                 load_reference_hash!("items/green-shell_5.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/green-shell"),
         },
         ItemReference {
             item: Item::Horn,
@@ -286,7 +302,7 @@ lazy_static! {
                 load_reference_hash!("items/horn_3.jpg"),
                 load_reference_hash!("items/horn_4.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/horn"),
         },
         ItemReference {
             item: Item::Lightning,
@@ -294,7 +310,7 @@ lazy_static! {
                 load_reference_hash!("items/lightning.jpg"),
                 load_reference_hash!("items/lightning_1.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/lightning"),
         },
         ItemReference {
             item: Item::MushroomDouble,
@@ -304,7 +320,7 @@ lazy_static! {
                 load_reference_hash!("items/mushroom-double_2.jpg"),
                 load_reference_hash!("items/mushroom-double_3.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/mushroom-double"),
         },
         ItemReference {
             item: Item::MushroomTriple,
@@ -315,7 +331,7 @@ lazy_static! {
                 load_reference_hash!("items/mushroom-triple_3.jpg"),
                 load_reference_hash!("items/mushroom-triple_4.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/mushroom-triple"),
         },
         ItemReference {
             item: Item::Mushroom,
@@ -326,7 +342,7 @@ lazy_static! {
                 load_reference_hash!("items/mushroom_3.jpg"),
                 load_reference_hash!("items/mushroom_4.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/mushroom"),
         },
         ItemReference {
             item: Item::PiranhaPlant,
@@ -335,12 +351,12 @@ lazy_static! {
                 load_reference_hash!("items/pirhana-plant_1.jpg"),
                 load_reference_hash!("items/pirhana-plant_2.jpg"),
             ],
-            threshold: 14,
+            threshold: threshold("items/pirhana-plant"),
         },
         ItemReference {
             item: Item::RedShellDouble,
             files: vec![load_reference_hash!("items/red-shell-double.jpg"),],
-            threshold: 12,
+            threshold: threshold("items/red-shell-double"),
         },
         ItemReference {
             item: Item::RedShellTriple,
@@ -348,7 +364,7 @@ lazy_static! {
                 load_reference_hash!("items/red-shell-triple.jpg"),
                 load_reference_hash!("items/red-shell-triple_1.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/red-shell-triple"),
         },
         ItemReference {
             item: Item::RedShell,
@@ -358,12 +374,12 @@ lazy_static! {
                 load_reference_hash!("items/red-shell_2.jpg"),
                 load_reference_hash!("items/red-shell_3.jpg"),
             ],
-            threshold: 16,
+            threshold: threshold("items/red-shell"),
         },
         ItemReference {
             item: Item::Squid,
             files: vec![load_reference_hash!("items/squid.jpg"),],
-            threshold: 12,
+            threshold: threshold("items/squid"),
         },
         ItemReference {
             item: Item::Star,
@@ -374,11 +390,19 @@ lazy_static! {
                 load_reference_hash!("items/star_3.jpg"),
                 load_reference_hash!("items/star_4.jpg"),
             ],
-            threshold: 12,
+            threshold: threshold("items/star"),
         },
     ];
 }
 
+pub(crate) fn load_references() {
+    lazy_static::initialize(&LAP_FLAG_REFERENCE);
+    lazy_static::initialize(&GO_REFERENCE);
+    lazy_static::initialize(&FINISH_REFERENCE);
+    lazy_static::initialize(&REFERENCE_HASHES);
+    lazy_static::initialize(&ITEM_HASHES);
+}
+
 fn player_vec_serializer<S: Serializer>(
     players: &Vec<Player>,
     serializer: S,
@@ -386,14 +410,7 @@ fn player_vec_serializer<S: Serializer>(
     let mut map = serializer.serialize_map(Some(players.len()))?;
 
     for player in players.iter() {
-        let name = match player.index {
-            0 => "player_one",
-            1 => "player_two",
-            2 => "player_three",
-            3 => "player_four",
-            _ => panic!("too many players! only four supported!"),
-        };
-
+        let name = super::player_name(player.index);
         map.serialize_entry(name, player)?;
     }
 
@@ -407,6 +424,12 @@ pub struct Race {
 
     #[serde(skip_serializing)]
     pub starting: bool,
+
+    /// Online races look the same as offline ones, so this can't be read off
+    /// the frame. It's filled in from the `Session`, which knows whether we
+    /// came from the online lobby.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub online: bool,
 }
 
 impl Display for Race {
@@ -424,10 +447,27 @@ impl Display for Race {
     }
 }
 
-const POSITION_CROP: [[u32; 2]; 4] = [[57, 239], [1167, 239], [57, 599], [1167, 599]];
-const LAP_FLAG_CROP: [[u32; 2]; 4] = [[114, 317], [1178, 317], [114, 677], [1178, 677]];
-const FINISH_CROP: [[u32; 2]; 4] = [[159, 127], [799, 127], [159, 487], [799, 487]];
-const ITEM_CROP: [[u32; 2]; 4] = [[99, 62], [1140, 62], [99, 422], [1140, 422]];
+pub(crate) const POSITION_CROP: [[u32; 2]; 4] = [[57, 239], [1167, 239], [57, 599], [1167, 599]];
+pub(crate) const POSITION_SIZE: [u32; 2] = [36, 54];
+pub(crate) const LAP_FLAG_CROP: [[u32; 2]; 4] = [[114, 317], [1178, 317], [114, 677], [1178, 677]];
+pub(crate) const LAP_FLAG_SIZE: [u32; 2] = [11, 11];
+pub(crate) const FINISH_CROP: [[u32; 2]; 4] = [[159, 127], [799, 127], [159, 487], [799, 487]];
+pub(crate) const FINISH_SIZE: [u32; 2] = [90, 38];
+pub(crate) const ITEM_CROP: [[u32; 2]; 4] = [[99, 62], [1140, 62], [99, 422], [1140, 422]];
+pub(crate) const ITEM_SIZE: [u32; 2] = [41, 41];
+// the countdown's "GO!", in the middle of the screen whatever the player count
+pub(crate) const GO_CROP: [u32; 4] = [573, 292, 39, 37];
+// the middle of each player's view, clear of the HUD
+const VIEW_CROP: [[u32; 2]; 4] = [[40, 60], [680, 60], [40, 420], [680, 420]];
+const VIEW_SIZE: [u32; 2] = [560, 260];
+// a view darker than this on average has faded out. even the darkest courses
+// are well above it
+const FADED_CUTOFF: u32 = 5_000;
+// the warning for a blue shell on its way flashes at the top of the leader's
+// view. it's the same picture as the item, so it's checked against the item's
+// references
+const WARNING_CROP: [[u32; 2]; 4] = [[300, 8], [940, 8], [300, 368], [940, 368]];
+const WARNING_SIZE: u32 = 41;
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -457,6 +497,29 @@ pub struct Player {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     item: Option<Item>,
+
+    /// The player's view has faded out, like it does while Lakitu brings them
+    /// back after falling off the course
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    faded: bool,
+
+    /// A blue shell is on its way. Only the player in first can see this
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    blue_shell_warning: bool,
+}
+
+impl Player {
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn position(&self) -> Option<u8> {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status == Status::Finished
+    }
 }
 
 impl Display for Player {
@@ -478,7 +541,7 @@ impl Display for Player {
 }
 
 impl Reference for Race {
-    fn process(frame: &image::DynamicImage) -> Option<Screen> {
+    fn process(frame: &Frame) -> Option<Screen> {
         let mut players: Vec<Player> = (0..4)
             .par_bridge()
             .filter(|p| check_player_exists(&frame, p))
@@ -489,11 +552,17 @@ impl Reference for Race {
 
                 let item = get_item(&frame, p);
 
+                let faded = is_faded(frame, p);
+
+                let blue_shell_warning = position == Some(1) && check_blue_shell_warning(frame, p);
+
                 Player {
                     index: p as u8,
                     position,
                     status,
                     item,
+                    faded,
+                    blue_shell_warning,
                 }
             })
             .collect();
@@ -506,42 +575,90 @@ impl Reference for Race {
 
         let starting = check_starting(&frame);
 
-        Some(Screen::Race(Race { players, starting }))
+        Some(Screen::Race(Race {
+            players,
+            starting,
+            online: false,
+        }))
     }
 
-    fn compare(frame: &image::DynamicImage) -> bool {
-        if !is_splitscreen(frame) {
+    fn compare(frame: &Frame) -> bool {
+        if !frame.is_splitscreen() {
             return false;
         }
 
         let width = frame.width();
-        let [r, g, b] = average_colors(&frame.crop_imm(width / 2 - 1, 48, 1, 8));
+        let [r, g, b] = average_colors(&*frame.view(width / 2 - 1, 48, 1, 8));
         let average = (r as u32 + g as u32 + b as u32) / 3;
 
         return average < 5000;
     }
+
+    fn regions() -> Vec<[u32; 4]> {
+        let region = |[x, y]: [u32; 2], [width, height]: [u32; 2]| [x, y, width, height];
+
+        (0..4)
+            .flat_map(|i| {
+                [
+                    region(POSITION_CROP[i], POSITION_SIZE),
+                    region(ITEM_CROP[i], ITEM_SIZE),
+                    region(LAP_FLAG_CROP[i], LAP_FLAG_SIZE),
+                    region(FINISH_CROP[i], FINISH_SIZE),
+                ]
+            })
+            .collect()
+    }
 }
 
 fn check_starting(frame: &image::DynamicImage) -> bool {
-    let image = frame.crop_imm(573, 292, 39, 37);
+    go_distance(frame) <= threshold("race/go")
+}
+
+fn go_distance(frame: &image::DynamicImage) -> u32 {
+    let [x, y, width, height] = GO_CROP;
+    let image = frame.crop_imm(x, y, width, height);
     let hash = hasher::hash_image(image);
 
-    GO_REFERENCE.dist(&hash) < 15
+    GO_REFERENCE.dist(&hash)
 }
 
-fn check_player_exists(frame: &image::DynamicImage, index: &usize) -> bool {
-    let [x, y] = LAP_FLAG_CROP[*index];
-    let image = frame.crop_imm(x, y, 11, 11);
+pub(crate) fn check_player_exists(frame: &Frame, index: &usize) -> bool {
+    frame.cached(region(LAP_FLAG_CROP[*index], LAP_FLAG_SIZE), || {
+        lap_flag_distance(frame, *index) <= threshold("race/lap_flag")
+    })
+}
+
+fn lap_flag_distance(frame: &image::DynamicImage, index: usize) -> u32 {
+    let [x, y] = LAP_FLAG_CROP[index];
+    let [width, height] = LAP_FLAG_SIZE;
+    let image = frame.crop_imm(x, y, width, height);
 
     let hash = hasher::hash_image(image);
 
-    return LAP_FLAG_REFERENCE.dist(&hash) < 20;
+    LAP_FLAG_REFERENCE.dist(&hash)
+}
+
+fn get_position(frame: &Frame, index: usize) -> Option<u8> {
+    frame.cached(region(POSITION_CROP[index], POSITION_SIZE), || {
+        nearest_position(frame, index)
+            .filter(|(_, min_dist)| min_dist <= &threshold("race/position"))
+            .map(|(position, _)| position)
+    })
 }
 
-fn get_position(frame: &image::DynamicImage, index: usize) -> Option<u8> {
+/// The position whose reference is closest to what's shown, and how close
+fn nearest_position(frame: &image::DynamicImage, index: usize) -> Option<(u8, u32)> {
+    position_distances(frame, index)
+        .into_iter()
+        .min_by(|(_, dist_a), (_, dist_b)| dist_a.cmp(dist_b))
+}
+
+/// How close the position is to each position's closest reference
+fn position_distances(frame: &image::DynamicImage, index: usize) -> Vec<(u8, u32)> {
     let [x, y] = POSITION_CROP[index];
+    let [width, height] = POSITION_SIZE;
 
-    let image = frame.crop_imm(x, y, 36, 54).grayscale();
+    let image = frame.crop_imm(x, y, width, height).grayscale();
     let res = hasher::hash_image(image);
 
     REFERENCE_HASHES
@@ -549,7 +666,7 @@ fn get_position(frame: &image::DynamicImage, index: usize) -> Option<u8> {
         .enumerate()
         .map(|(i, hash)| {
             (
-                i,
+                (i as u8) + 1,
                 hash.files
                     .iter()
                     .map(|f| f.dist(&res))
@@ -557,44 +674,42 @@ fn get_position(frame: &image::DynamicImage, index: usize) -> Option<u8> {
                     .unwrap_or(u32::MAX),
             )
         })
-        .filter(|(_, min_dist)| min_dist < &16)
-        .min_by(|(_, dist_a), (_, dist_b)| dist_a.cmp(dist_b))
-        .map(|(i, _)| (i as u8) + 1)
+        .collect()
 }
 
 // These are a bit too hard-coded right now, but that's okay
-fn check_player_one(frame: &image::DynamicImage) -> bool {
+fn check_player_one(frame: &Frame) -> bool {
     // check three pixels are pretty close
-    let check_slice = frame.crop_imm(112, 40, 10, 2);
-    let [average_red, average_green, _] = average_colors(&check_slice);
+    let check_slice = frame.view(112, 40, 10, 2);
+    let [average_red, average_green, _] = average_colors(&*check_slice);
 
     average_red > COLOR_THRESHOLD && average_green > COLOR_THRESHOLD
 }
 
-fn check_player_two(frame: &image::DynamicImage) -> bool {
+fn check_player_two(frame: &Frame) -> bool {
     // check three pixels are pretty close
-    let check_slice = frame.crop_imm(1154, 40, 10, 2);
-    let [_, average_green, average_blue] = average_colors(&check_slice);
+    let check_slice = frame.view(1154, 40, 10, 2);
+    let [_, average_green, average_blue] = average_colors(&*check_slice);
 
     average_green > COLOR_THRESHOLD && average_blue > COLOR_THRESHOLD
 }
 
-fn check_player_three(frame: &image::DynamicImage) -> bool {
+fn check_player_three(frame: &Frame) -> bool {
     // check three pixels are pretty close
-    let check_slice = frame.crop_imm(112, 400, 10, 2);
-    let [average_red, _, _] = average_colors(&check_slice);
+    let check_slice = frame.view(112, 400, 10, 2);
+    let [average_red, _, _] = average_colors(&*check_slice);
 
     average_red > COLOR_THRESHOLD
 }
-fn check_player_four(frame: &image::DynamicImage) -> bool {
+fn check_player_four(frame: &Frame) -> bool {
     // check three pixels are pretty close
-    let check_slice = frame.crop_imm(1154, 400, 10, 2);
-    let [_, average_green, _] = average_colors(&check_slice);
+    let check_slice = frame.view(1154, 400, 10, 2);
+    let [_, average_green, _] = average_colors(&*check_slice);
 
     average_green > COLOR_THRESHOLD
 }
 
-fn check_player(frame: &image::DynamicImage, index: usize) -> bool {
+fn check_player(frame: &Frame, index: usize) -> bool {
     match index {
         0 => check_player_one(frame),
         1 => check_player_two(frame),
@@ -604,13 +719,27 @@ fn check_player(frame: &image::DynamicImage, index: usize) -> bool {
     }
 }
 
-fn get_item(frame: &image::DynamicImage, index: usize) -> Option<Item> {
+fn get_item(frame: &Frame, index: usize) -> Option<Item> {
     if !check_player(frame, index) {
         return None;
     }
 
+    frame.cached(region(ITEM_CROP[index], ITEM_SIZE), || {
+        item_distances(frame, index)
+            .into_iter()
+            .filter(|(h, dist)| dist <= &h.threshold)
+            // there is maybe something we can do here with threshold - dist
+            .min_by(|(_, dist_a), (_, dist_b)| dist_a.cmp(dist_b))
+            .map(|(hash, _)| hash)
+            .map(|hash| hash.item)
+    })
+}
+
+/// How close the item slot is to each item's closest reference
+fn item_distances(frame: &image::DynamicImage, index: usize) -> Vec<(&'static ItemReference, u32)> {
     let [x, y] = ITEM_CROP[index];
-    let image = frame.crop_imm(x, y, 41, 41);
+    let [width, height] = ITEM_SIZE;
+    let image = frame.crop_imm(x, y, width, height);
 
     let res = hasher::hash_image(image);
 
@@ -626,29 +755,132 @@ fn get_item(frame: &image::DynamicImage, index: usize) -> Option<Item> {
                     .unwrap_or(u32::MAX),
             )
         })
-        .filter(|(h, dist)| dist <= &(h.threshold as u32))
-        // there is maybe something we can do here with threshold - dist
-        .min_by(|(_, dist_a), (_, dist_b)| dist_a.cmp(dist_b))
-        .map(|(hash, _)| hash)
-        .map(|hash| hash.item)
+        .collect()
 }
 
-fn get_status(frame: &image::DynamicImage, index: usize) -> Status {
+fn get_status(frame: &Frame, index: usize) -> Status {
+    frame.cached(region(FINISH_CROP[index], FINISH_SIZE), || {
+        if finish_distance(frame, index) <= threshold("race/finish") {
+            Status::Finished
+        } else {
+            Status::Racing
+        }
+    })
+}
+
+fn finish_distance(frame: &image::DynamicImage, index: usize) -> u32 {
     let [x, y] = FINISH_CROP[index];
+    let [width, height] = FINISH_SIZE;
 
-    let image = frame.crop_imm(x, y, 90, 38);
+    let image = frame.crop_imm(x, y, width, height);
 
     let res = hasher::hash_image(image);
 
-    if FINISH_REFERENCE.dist(&res) < 10 {
-        Status::Finished
-    } else {
-        Status::Racing
-    }
+    FINISH_REFERENCE.dist(&res)
+}
+
+/// A crop's position and size, as a region
+fn region([x, y]: [u32; 2], [width, height]: [u32; 2]) -> [u32; 4] {
+    [x, y, width, height]
+}
+
+/// The distances behind everything `process` reads, for each player whether
+/// or not the player is there
+pub(crate) fn samples(frame: &image::DynamicImage) -> Vec<Sample> {
+    let go = Sample {
+        reference: "race/go".into(),
+        region: GO_CROP,
+        player: None,
+        label: None,
+        distance: go_distance(frame),
+    };
+
+    (0..4)
+        .flat_map(|index| {
+            let player = Some(index as u8);
+            let mut samples = vec![
+                Sample {
+                    reference: "race/lap_flag".into(),
+                    region: region(LAP_FLAG_CROP[index], LAP_FLAG_SIZE),
+                    player,
+                    label: None,
+                    distance: lap_flag_distance(frame, index),
+                },
+                Sample {
+                    reference: "race/finish".into(),
+                    region: region(FINISH_CROP[index], FINISH_SIZE),
+                    player,
+                    label: None,
+                    distance: finish_distance(frame, index),
+                },
+                Sample {
+                    reference: "race/blue_shell_warning".into(),
+                    region: region(WARNING_CROP[index], [WARNING_SIZE, WARNING_SIZE]),
+                    player,
+                    label: None,
+                    distance: warning_distance(frame, index),
+                },
+            ];
+
+            // only the nearest position is ever read, so it's the only one
+            // whose distance says anything about the threshold
+            samples.extend(
+                nearest_position(frame, index).map(|(position, distance)| Sample {
+                    reference: "race/position".into(),
+                    region: region(POSITION_CROP[index], POSITION_SIZE),
+                    player,
+                    label: Some(position.to_string()),
+                    distance,
+                }),
+            );
+
+            samples.extend(item_distances(frame, index).into_iter().map(
+                |(reference, distance)| {
+                    let name = reference.item.name();
+                    Sample {
+                        reference: format!("items/{name}"),
+                        region: region(ITEM_CROP[index], ITEM_SIZE),
+                        player,
+                        label: Some(name),
+                        distance,
+                    }
+                },
+            ));
+
+            samples
+        })
+        .chain([go])
+        .collect()
+}
+
+fn check_blue_shell_warning(frame: &image::DynamicImage, index: usize) -> bool {
+    warning_distance(frame, index) <= threshold("race/blue_shell_warning")
+}
+
+/// How close the top of the view is to the nearest blue shell reference
+fn warning_distance(frame: &image::DynamicImage, index: usize) -> u32 {
+    let [x, y] = WARNING_CROP[index];
+    let hash = hasher::hash_image(frame.crop_imm(x, y, WARNING_SIZE, WARNING_SIZE));
+
+    ITEM_HASHES
+        .iter()
+        .filter(|reference| reference.item == Item::BlueShell)
+        .flat_map(|reference| reference.files.iter())
+        .map(|file| file.dist(&hash))
+        .min()
+        .unwrap_or(u32::MAX)
+}
+
+fn is_faded(frame: &Frame, index: usize) -> bool {
+    let [x, y] = VIEW_CROP[index];
+    let [width, height] = VIEW_SIZE;
+
+    get_overall_average(&*frame.view(x, y, width, height)) < FADED_CUTOFF
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::frame::Frame;
     use crate::reference::Reference;
     use pretty_assertions::assert_eq;
 
@@ -661,6 +893,8 @@ mod tests {
                 position: None,
                 status: Status::Racing,
                 item: None,
+                faded: false,
+                blue_shell_warning: false,
             }
         };
         ($index:expr, $pos:expr) => {
@@ -669,6 +903,8 @@ mod tests {
                 position: Some($pos),
                 status: Status::Racing,
                 item: None,
+                faded: false,
+                blue_shell_warning: false,
             }
         };
         ($index:expr, $pos:expr, $item:ident) => {
@@ -677,6 +913,8 @@ mod tests {
                 position: Some($pos),
                 status: Status::Racing,
                 item: Some(Item::$item),
+                faded: false,
+                blue_shell_warning: false,
             }
         };
         ($index:expr, $pos:expr, $item:ident, $status:ident) => {
@@ -685,6 +923,8 @@ mod tests {
                 position: Some($pos),
                 status: Status::$status,
                 item: Some(Item::$item),
+                faded: false,
+                blue_shell_warning: false,
             }
         };
     }
@@ -695,7 +935,7 @@ mod tests {
             fn $name() {
                 let image_data = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/spec-data/screens/race/", stringify!($name), ".jpg"));
                 let frame = image::load_from_memory(image_data).expect("failed to open image");
-                let result = Race::process(&frame);
+                let result = Race::process(&Frame::new(&frame));
 
                 assert_eq!(
                     Some(super::Screen::Race(Race {
@@ -703,6 +943,7 @@ mod tests {
                             $($players),+
                         ],
                         starting: false,
+                        online: false,
                     })),
                     result,
                 );
@@ -740,14 +981,18 @@ mod tests {
             index: 0,
             status: Status::Finished,
             item: None,
-            position: Some(6)
+            position: Some(6),
+            faded: false,
+            blue_shell_warning: false,
         },
         player!(1, 2),
         Player {
             index: 2,
             status: Status::Finished,
             item: None,
-            position: Some(4)
+            position: Some(4),
+            faded: false,
+            blue_shell_warning: false,
         },
         player!(3, 9, Mushroom),
     );
@@ -761,7 +1006,9 @@ mod tests {
             index: 3,
             status: Status::Racing,
             item: Some(Item::GreenShell),
-            position: None // mid transition
+            position: None, // mid transition
+            faded: false,
+            blue_shell_warning: false,
         },
     );
 
@@ -786,4 +1033,24 @@ mod tests {
         player!(1, 10, RedShell),
         player!(2, 6),
     );
+
+    #[test]
+    fn cached_regions_are_read_the_same() {
+        let cache = std::sync::Arc::new(crate::RegionCache::new());
+
+        for name in 1..=8 {
+            let path = format!(
+                "{}/spec-data/screens/race/test_{name}.jpg",
+                env!("CARGO_MANIFEST_DIR")
+            );
+            let image = image::open(path).expect("failed to open image");
+
+            let expected = Race::process(&Frame::new(&image));
+            // the second time through, it's all from the cache
+            for _ in 0..2 {
+                let frame = Frame::new(&image).with_cache(&cache);
+                assert_eq!(Race::process(&frame), expected, "test_{name}");
+            }
+        }
+    }
 }
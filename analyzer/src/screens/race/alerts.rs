@@ -0,0 +1,183 @@
+use super::events::{Incident, RaceIncident, LIGHTNING_WINDOW};
+use super::{Item, Race};
+use serde::Serialize;
+
+// Blue shells and lightning change the whole race, so they get called out on
+// their own as soon as we know about them, rather than waiting to be picked
+// out of the incidents.
+
+// the blue shell warning flashes, so it has to be gone for this many frames
+// before it counts as a new shell
+const WARNING_GAP: usize = 30;
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(tag = "alert", rename_all = "snake_case")]
+pub enum Alert {
+    /// A blue shell left someone's item slot
+    BlueShell {
+        #[serde(
+            serialize_with = "crate::screens::serialize_player",
+            skip_serializing_if = "Option::is_none"
+        )]
+        by: Option<u8>,
+    },
+    /// Lightning struck. `by` is only known when it was one of the players
+    Lightning {
+        #[serde(
+            serialize_with = "crate::screens::serialize_player",
+            skip_serializing_if = "Option::is_none"
+        )]
+        by: Option<u8>,
+    },
+    /// The player in first is being warned about a blue shell coming for them
+    BlueShellIncoming {
+        #[serde(serialize_with = "crate::screens::serialize_index")]
+        player: u8,
+    },
+}
+
+impl Alert {
+    pub fn event_type(&self) -> &'static str {
+        "alert"
+    }
+}
+
+/// Picks the alerts out of each frame of a race, and the incidents the
+/// `Tracker` found in it
+#[derive(Debug, Default)]
+pub struct Alerts {
+    frame: usize,
+    warned_at: [Option<usize>; 4],
+    lightning_at: Option<usize>,
+}
+
+impl Alerts {
+    pub fn new() -> Self {
+        Alerts::default()
+    }
+
+    pub fn update(&mut self, race: &Race, incidents: &[RaceIncident]) -> Vec<Alert> {
+        self.frame += 1;
+
+        let mut alerts = vec![];
+        for incident in incidents {
+            let alert = match incident.incident {
+                Incident::UsedItem {
+                    item: Item::BlueShell,
+                } => Alert::BlueShell {
+                    by: Some(incident.player),
+                },
+                Incident::UsedItem {
+                    item: Item::Lightning,
+                } => Alert::Lightning {
+                    by: Some(incident.player),
+                },
+                // everyone hit by the same CPU's lightning shows up
+                // separately, but it's only one strike
+                Incident::HitBy {
+                    item: Some(Item::Lightning),
+                    by: None,
+                } if !self.struck_recently() => Alert::Lightning { by: None },
+                _ => continue,
+            };
+
+            if let Alert::Lightning { .. } = alert {
+                self.lightning_at = Some(self.frame);
+            }
+            alerts.push(alert);
+        }
+
+        for player in race.players.iter().filter(|p| p.blue_shell_warning) {
+            let warned_at = &mut self.warned_at[player.index as usize];
+            if !warned_at.is_some_and(|at| self.frame - at <= WARNING_GAP) {
+                alerts.push(Alert::BlueShellIncoming {
+                    player: player.index,
+                });
+            }
+            *warned_at = Some(self.frame);
+        }
+
+        alerts
+    }
+
+    fn struck_recently(&self) -> bool {
+        self.lightning_at
+            .is_some_and(|at| self.frame - at <= LIGHTNING_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alert, Alerts};
+    use crate::screens::race::events::{Incident, RaceIncident};
+    use crate::screens::race::{Item, Player, Race, Status};
+    use pretty_assertions::assert_eq;
+
+    fn race(warning: bool) -> Race {
+        Race {
+            players: vec![Player {
+                index: 1,
+                position: Some(1),
+                status: Status::Racing,
+                item: None,
+                faded: false,
+                blue_shell_warning: warning,
+            }],
+            starting: false,
+            online: false,
+        }
+    }
+
+    fn incident(player: u8, incident: Incident) -> RaceIncident {
+        RaceIncident { player, incident }
+    }
+
+    #[test]
+    fn firing_a_blue_shell() {
+        let mut alerts = Alerts::new();
+
+        let fired = alerts.update(
+            &race(false),
+            &[incident(
+                2,
+                Incident::UsedItem {
+                    item: Item::BlueShell,
+                },
+            )],
+        );
+
+        assert_eq!(fired, vec![Alert::BlueShell { by: Some(2) }]);
+    }
+
+    #[test]
+    fn cpu_lightning_is_one_alert() {
+        let mut alerts = Alerts::new();
+        let hit = |player| {
+            incident(
+                player,
+                Incident::HitBy {
+                    item: Some(Item::Lightning),
+                    by: None,
+                },
+            )
+        };
+
+        let first = alerts.update(&race(false), &[hit(0), hit(1)]);
+        let second = alerts.update(&race(false), &[hit(2)]);
+
+        assert_eq!(first, vec![Alert::Lightning { by: None }]);
+        assert_eq!(second, vec![]);
+    }
+
+    #[test]
+    fn a_flashing_warning_is_one_alert() {
+        let mut alerts = Alerts::new();
+
+        let raised: Vec<_> = [true, false, true, false, true]
+            .into_iter()
+            .flat_map(|warning| alerts.update(&race(warning), &[]))
+            .collect();
+
+        assert_eq!(raised, vec![Alert::BlueShellIncoming { player: 1 }]);
+    }
+}
@@ -0,0 +1,530 @@
+use super::{Item, Player, Race};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+// A single frame only shows where everyone is and what they're holding. What
+// happened to get there comes from watching the HUD change between frames:
+//
+// - an item disappearing from a player's slot means they used it
+// - a player losing a few places in quick succession means they were hit
+// - a player's view fading out means they fell off the course
+//
+// Lightning makes everyone else drop what they're holding, so a slot clearing
+// just after a player fires one is a hit rather than the player using their
+// item. A CPU's lightning can only be guessed at: when several slots clear at
+// once and nobody is left holding anything, that's taken to be lightning. This
+// is a heuristic - there's no frame of a CPU's lightning in the corpus to
+// check it against, and players who all use their items together look the
+// same.
+
+// a reading has to hold for this many frames before we believe it. item and
+// position reads flicker while they animate
+const SETTLE_FRAMES: u8 = 3;
+// losing this many places within `HIT_WINDOW` frames is more than being
+// overtaken
+const HIT_POSITION_DROP: u8 = 2;
+const HIT_WINDOW: usize = 30;
+// at least this many players have to clear their slots at once, with nobody
+// left holding anything, to guess that a CPU used lightning
+const LIGHTNING_MIN_PLAYERS: usize = 2;
+// slots that clear this many frames after lightning were cleared by it, even
+// if they settled a little later than the rest
+pub(super) const LIGHTNING_WINDOW: usize = 15;
+
+/// Something that happened to a player during a race
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(tag = "incident", rename_all = "snake_case")]
+pub enum Incident {
+    UsedItem {
+        item: Item,
+    },
+    HitBy {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        item: Option<Item>,
+
+        /// Which player did it, when they're one of the players on screen
+        #[serde(
+            serialize_with = "crate::screens::serialize_player",
+            skip_serializing_if = "Option::is_none"
+        )]
+        by: Option<u8>,
+    },
+    FellOff,
+}
+
+impl Incident {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Incident::UsedItem { .. } => "used_item",
+            Incident::HitBy { .. } => "hit_by",
+            Incident::FellOff => "fell_off",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct RaceIncident {
+    #[serde(serialize_with = "crate::screens::serialize_index")]
+    pub player: u8,
+
+    #[serde(flatten)]
+    pub incident: Incident,
+}
+
+/// What the HUD says about a player, once it's stopped changing
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Reading {
+    position: Option<u8>,
+    item: Option<Item>,
+    faded: bool,
+}
+
+impl From<&Player> for Reading {
+    fn from(player: &Player) -> Self {
+        Reading {
+            position: player.position,
+            item: player.item,
+            faded: player.faded,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Track {
+    settled: Reading,
+    pending: Reading,
+    count: u8,
+    /// Recent settled positions, and the frame they settled on
+    positions: VecDeque<(usize, u8)>,
+}
+
+/// When lightning last struck, and who fired it if it was one of the players
+#[derive(Debug, Clone, Copy)]
+struct Lightning {
+    at: usize,
+    by: Option<u8>,
+}
+
+/// Follows each player's HUD from frame to frame during a race, and reports
+/// what happened to them. Readings have to settle before they count, so a
+/// single misread frame can't produce an incident.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    frame: usize,
+    players: [Option<Track>; 4],
+    lightning: Option<Lightning>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Tracker::default()
+    }
+
+    pub fn update(&mut self, race: &Race) -> Vec<RaceIncident> {
+        self.frame += 1;
+
+        let mut changes = vec![];
+        for player in race.players.iter() {
+            let reading = Reading::from(player);
+            let Some(track) = &mut self.players[player.index as usize] else {
+                // the first time we see a player is where they start from
+                self.players[player.index as usize] = Some(Track {
+                    settled: reading,
+                    pending: reading,
+                    count: SETTLE_FRAMES,
+                    positions: reading
+                        .position
+                        .map(|p| (self.frame, p))
+                        .into_iter()
+                        .collect(),
+                });
+                continue;
+            };
+
+            if reading == track.pending {
+                track.count = track.count.saturating_add(1);
+            } else {
+                track.pending = reading;
+                track.count = 1;
+            }
+
+            if track.count != SETTLE_FRAMES || track.pending == track.settled {
+                continue;
+            }
+
+            // a position that can't be read right now hasn't changed
+            let after = Reading {
+                position: track.pending.position.or(track.settled.position),
+                ..track.pending
+            };
+            changes.push((player.index, track.settled, after));
+            track.settled = after;
+        }
+
+        let cleared = changes
+            .iter()
+            .filter(|(_, before, after)| before.item.is_some() && after.item.is_none())
+            .count();
+        // anyone still holding something wasn't hit
+        let emptied = race.players.iter().all(|player| player.item.is_none());
+        let fired_by = fired_lightning(&changes);
+        if fired_by.is_some() || (cleared >= LIGHTNING_MIN_PLAYERS && emptied) {
+            self.lightning = Some(Lightning {
+                at: self.frame,
+                by: fired_by,
+            });
+        }
+        let lightning = self
+            .lightning
+            .filter(|lightning| self.frame - lightning.at <= LIGHTNING_WINDOW);
+
+        let present: Vec<u8> = race.players.iter().map(|player| player.index).collect();
+        let mut incidents = detect(&changes, &present, lightning);
+        incidents.extend(
+            changes
+                .iter()
+                .filter_map(|(index, _, after)| self.check_hit(*index, *after)),
+        );
+
+        incidents
+    }
+
+    /// Looks for a player losing places quickly, which is what happens when
+    /// they're hit by a shell (or anything else that spins them out)
+    fn check_hit(&mut self, index: u8, after: Reading) -> Option<RaceIncident> {
+        let frame = self.frame;
+        let track = self.players[index as usize].as_mut()?;
+        let position = after.position?;
+
+        while let Some((settled_at, _)) = track.positions.front() {
+            if frame - settled_at <= HIT_WINDOW {
+                break;
+            }
+            track.positions.pop_front();
+        }
+        if track.positions.back().map(|(_, p)| *p) != Some(position) {
+            track.positions.push_back((frame, position));
+        }
+
+        let best = track.positions.iter().map(|(_, p)| *p).min()?;
+        if position < best + HIT_POSITION_DROP || after.faded {
+            return None;
+        }
+
+        // start again from here, so the same hit isn't reported twice
+        track.positions.clear();
+        track.positions.push_back((frame, position));
+
+        Some(RaceIncident {
+            player: index,
+            incident: Incident::HitBy {
+                item: None,
+                by: None,
+            },
+        })
+    }
+}
+
+/// Works out what happened from readings that settled on the same frame, as
+/// `(player, before, after)`, with `present` being every player in the race
+fn detect(
+    changes: &[(u8, Reading, Reading)],
+    present: &[u8],
+    lightning: Option<Lightning>,
+) -> Vec<RaceIncident> {
+    let mut incidents = vec![];
+    let fired_by = fired_lightning(changes);
+
+    for (index, before, after) in changes {
+        let incident = |incident| RaceIncident {
+            player: *index,
+            incident,
+        };
+        let cleared = before.item.is_some() && after.item.is_none();
+
+        if !before.faded && after.faded {
+            incidents.push(incident(Incident::FellOff));
+        }
+
+        if Some(*index) == fired_by {
+            incidents.push(incident(Incident::UsedItem {
+                item: Item::Lightning,
+            }));
+        } else if let (true, Some(lightning)) = (cleared, lightning) {
+            // a player's lightning has already been counted against everyone
+            if lightning.by.is_none() {
+                incidents.push(incident(Incident::HitBy {
+                    item: Some(Item::Lightning),
+                    by: None,
+                }));
+            }
+        } else if let Some(item) = used(before.item, after.item) {
+            incidents.push(incident(Incident::UsedItem { item }));
+        }
+    }
+
+    // everyone else gets hit by a player's lightning, whether they were
+    // holding anything or not
+    if let Some(by) = fired_by {
+        incidents.extend(
+            present
+                .iter()
+                .copied()
+                .filter(|index| *index != by)
+                .map(|player| RaceIncident {
+                    player,
+                    incident: Incident::HitBy {
+                        item: Some(Item::Lightning),
+                        by: Some(by),
+                    },
+                }),
+        );
+    }
+
+    incidents
+}
+
+/// The player whose lightning just left their slot, if any
+fn fired_lightning(changes: &[(u8, Reading, Reading)]) -> Option<u8> {
+    changes
+        .iter()
+        .find(|(_, before, after)| before.item == Some(Item::Lightning) && after.item.is_none())
+        .map(|(index, _, _)| *index)
+}
+
+/// The item that was used going from holding `before` to holding `after`.
+/// Doubles and triples go down one at a time.
+fn used(before: Option<Item>, after: Option<Item>) -> Option<Item> {
+    let before = before?;
+    let single = match before {
+        Item::BananaDouble | Item::BananaTriple => Item::Banana,
+        Item::GreenShellDouble | Item::GreenShellTriple => Item::GreenShell,
+        Item::MushroomDouble | Item::MushroomTriple => Item::Mushroom,
+        Item::RedShellDouble | Item::RedShellTriple => Item::RedShell,
+        item => item,
+    };
+
+    let one_less = match before {
+        Item::BananaTriple => Some(Item::BananaDouble),
+        Item::BananaDouble => Some(Item::Banana),
+        Item::GreenShellTriple => Some(Item::GreenShellDouble),
+        Item::GreenShellDouble => Some(Item::GreenShell),
+        Item::MushroomTriple => Some(Item::MushroomDouble),
+        Item::MushroomDouble => Some(Item::Mushroom),
+        Item::RedShellTriple => Some(Item::RedShellDouble),
+        Item::RedShellDouble => Some(Item::RedShell),
+        _ => None,
+    };
+
+    if after.is_none() || after == one_less {
+        Some(single)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Incident, RaceIncident, Tracker};
+    use crate::screens::race::{Item, Player, Race, Status};
+    use pretty_assertions::assert_eq;
+
+    fn player(index: u8, position: u8, item: Option<Item>) -> Player {
+        Player {
+            index,
+            position: Some(position),
+            status: Status::Racing,
+            item,
+            faded: false,
+            blue_shell_warning: false,
+        }
+    }
+
+    fn race(players: Vec<Player>) -> Race {
+        Race {
+            players,
+            starting: false,
+            online: false,
+        }
+    }
+
+    /// Feeds each race in for a few frames, so the readings settle
+    fn run(races: &[Race]) -> Vec<RaceIncident> {
+        let mut tracker = Tracker::new();
+
+        races
+            .iter()
+            .flat_map(|race| std::iter::repeat_n(race, 3))
+            .flat_map(|race| tracker.update(race))
+            .collect()
+    }
+
+    #[test]
+    fn using_an_item() {
+        let incidents = run(&[
+            race(vec![player(0, 3, Some(Item::MushroomTriple))]),
+            race(vec![player(0, 3, Some(Item::MushroomDouble))]),
+            race(vec![player(0, 2, None)]),
+        ]);
+
+        assert_eq!(
+            incidents,
+            vec![
+                RaceIncident {
+                    player: 0,
+                    incident: Incident::UsedItem {
+                        item: Item::Mushroom
+                    }
+                },
+                RaceIncident {
+                    player: 0,
+                    incident: Incident::UsedItem {
+                        item: Item::Mushroom
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn single_frame_misreads_are_ignored() {
+        let mut tracker = Tracker::new();
+        let holding = race(vec![player(0, 3, Some(Item::GreenShell))]);
+        let misread = race(vec![player(0, 9, None)]);
+
+        let incidents: Vec<_> = [&holding, &holding, &misread, &holding, &holding]
+            .iter()
+            .flat_map(|race| tracker.update(race))
+            .collect();
+
+        assert_eq!(incidents, vec![]);
+    }
+
+    #[test]
+    fn losing_places_quickly_is_a_hit() {
+        let incidents = run(&[
+            race(vec![player(0, 3, None)]),
+            race(vec![player(0, 4, None)]),
+            race(vec![player(0, 6, None)]),
+        ]);
+
+        assert_eq!(
+            incidents,
+            vec![RaceIncident {
+                player: 0,
+                incident: Incident::HitBy {
+                    item: None,
+                    by: None
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn lightning_hits_everyone_else() {
+        let incidents = run(&[
+            race(vec![
+                player(0, 8, Some(Item::Lightning)),
+                player(1, 2, Some(Item::Banana)),
+                player(2, 5, None),
+            ]),
+            race(vec![
+                player(0, 8, None),
+                player(1, 2, None),
+                player(2, 5, None),
+            ]),
+        ]);
+
+        assert_eq!(
+            incidents,
+            vec![
+                RaceIncident {
+                    player: 0,
+                    incident: Incident::UsedItem {
+                        item: Item::Lightning
+                    }
+                },
+                RaceIncident {
+                    player: 1,
+                    incident: Incident::HitBy {
+                        item: Some(Item::Lightning),
+                        by: Some(0)
+                    }
+                },
+                RaceIncident {
+                    player: 2,
+                    incident: Incident::HitBy {
+                        item: Some(Item::Lightning),
+                        by: Some(0)
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn slots_clearing_together_is_lightning() {
+        let incidents = run(&[
+            race(vec![
+                player(0, 3, Some(Item::Banana)),
+                player(1, 6, Some(Item::GreenShell)),
+                player(2, 9, None),
+            ]),
+            race(vec![
+                player(0, 3, None),
+                player(1, 6, None),
+                player(2, 9, None),
+            ]),
+        ]);
+
+        let hit = Incident::HitBy {
+            item: Some(Item::Lightning),
+            by: None,
+        };
+        assert_eq!(
+            incidents,
+            vec![
+                RaceIncident {
+                    player: 0,
+                    incident: hit
+                },
+                RaceIncident {
+                    player: 1,
+                    incident: hit
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn someone_still_holding_an_item_rules_out_lightning() {
+        let incidents = run(&[
+            race(vec![
+                player(0, 3, Some(Item::Banana)),
+                player(1, 6, Some(Item::GreenShell)),
+                player(2, 9, Some(Item::Mushroom)),
+            ]),
+            race(vec![
+                player(0, 3, None),
+                player(1, 6, None),
+                player(2, 9, Some(Item::Mushroom)),
+            ]),
+        ]);
+
+        assert_eq!(
+            incidents,
+            vec![
+                RaceIncident {
+                    player: 0,
+                    incident: Incident::UsedItem { item: Item::Banana }
+                },
+                RaceIncident {
+                    player: 1,
+                    incident: Incident::UsedItem {
+                        item: Item::GreenShell
+                    }
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use serde::Serialize;
+
+// The minimap sits in the middle of the screen, across all four views. Every
+// racer's icon is on it, and the players' icons have a ring around them in
+// their colour. Finding those rings gives where each player is on the map,
+// and watching where they go gives the shape of the track, which is enough to
+// work out how far around it everyone is.
+//
+// Scanning the map for rings on every frame isn't cheap, which is why this
+// is behind the `minimap` feature.
+
+// the area the map (and the icons on it) can be drawn in
+const MAP_CROP: [u32; 4] = [480, 180, 320, 360];
+// the rings are thin, and usually partly covered by other icons, but even
+// then there's more than this much of one showing
+const MIN_RING_PIXELS: usize = 40;
+// how far apart the points along the route are, in pixels
+const ROUTE_SPACING: f32 = 6.0;
+// a marker further than this from the route is somewhere we haven't been yet
+const ROUTE_REACH: f32 = ROUTE_SPACING * 2.0;
+// going from this far around the route to the start again is a new lap
+const LAP_EDGE: f32 = 0.2;
+
+/// Where a player's icon is on the minimap, in pixels from the top left of
+/// the area the map is drawn in
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct Marker {
+    #[serde(serialize_with = "crate::screens::serialize_index")]
+    pub player: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Finds each player's ring on the minimap of a 1280x720 frame. Players
+/// whose icon is covered up by someone else's are left out.
+pub fn read(frame: &image::DynamicImage) -> Vec<Marker> {
+    let [x, y, width, height] = MAP_CROP;
+    let map = frame.crop_imm(x, y, width, height).to_rgb8();
+
+    (0..4)
+        .filter_map(|player| {
+            let ring: Vec<bool> = map
+                .pixels()
+                .map(|p| ring_color(p.0) == Some(player))
+                .collect();
+
+            let [left, top, right, bottom] = largest_blob(&ring, width, height)?;
+
+            Some(Marker {
+                player,
+                x: (left + right) / 2,
+                y: (top + bottom) / 2,
+            })
+        })
+        .collect()
+}
+
+/// The player whose ring is this colour. These are a lot stricter than
+/// `player_color`, as the characters' icons are full of similar colours.
+fn ring_color([r, g, b]: [u8; 3]) -> Option<u8> {
+    if r > 220 && (190..=235).contains(&g) && b < 60 {
+        Some(0)
+    } else if r < 60 && g > 200 && b > 220 {
+        Some(1)
+    } else if r > 220 && (60..130).contains(&g) && (60..130).contains(&b) {
+        Some(2)
+    } else if (100..170).contains(&r) && g > 210 && b < 50 {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// The bounds of the biggest group of touching pixels in `mask`, as
+/// `[left, top, right, bottom]`
+fn largest_blob(mask: &[bool], width: u32, height: u32) -> Option<[u32; 4]> {
+    let mut seen = vec![false; mask.len()];
+    let mut best: Option<(usize, [u32; 4])> = None;
+
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut size = 0;
+        let mut bounds = [u32::MAX, u32::MAX, 0, 0];
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            size += 1;
+            bounds = [
+                bounds[0].min(x),
+                bounds[1].min(y),
+                bounds[2].max(x),
+                bounds[3].max(y),
+            ];
+
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                    continue;
+                }
+                let n = (ny * width as i64 + nx) as usize;
+                if mask[n] && !seen[n] {
+                    seen[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+
+        if size >= MIN_RING_PIXELS && best.is_none_or(|(most, _)| size > most) {
+            best = Some((size, bounds));
+        }
+    }
+
+    best.map(|(_, bounds)| bounds)
+}
+
+/// How far around the track a player is
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct Standing {
+    #[serde(serialize_with = "crate::screens::serialize_index")]
+    pub player: u8,
+
+    /// Laps completed, plus how far around the current one they are
+    pub progress: f32,
+
+    /// How far behind the leading player they are, in laps. CPUs aren't on
+    /// the map in a way we can follow, so this is only between players.
+    pub gap: f32,
+}
+
+/// Follows the players around the minimap over a race. The track is learnt
+/// from where the players go, so until the leader has been all the way around
+/// once, progress is measured against as much of the track as we've seen.
+#[derive(Debug, Default)]
+pub struct Progress {
+    route: Vec<[f32; 2]>,
+    players: [Option<Lap>; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Lap {
+    laps: u32,
+    /// The point along the route the player was last seen at
+    at: usize,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Progress::default()
+    }
+
+    pub fn update(&mut self, markers: &[Marker]) -> Vec<Standing> {
+        let mut progress = vec![];
+
+        for marker in markers {
+            let at = self.locate([marker.x as f32, marker.y as f32]);
+            let length = self.route.len() as f32;
+
+            let lap = match self.players[marker.player as usize] {
+                None => Lap { laps: 0, at },
+                Some(lap) => {
+                    let before = lap.at as f32 / length;
+                    let after = at as f32 / length;
+
+                    let laps = if before > 1.0 - LAP_EDGE && after < LAP_EDGE {
+                        lap.laps + 1
+                    } else if before < LAP_EDGE && after > 1.0 - LAP_EDGE {
+                        // backing over the line, or a bad read
+                        lap.laps.saturating_sub(1)
+                    } else {
+                        lap.laps
+                    };
+
+                    Lap { laps, at }
+                }
+            };
+            self.players[marker.player as usize] = Some(lap);
+
+            progress.push((marker.player, lap.laps as f32 + at as f32 / length));
+        }
+
+        let leader = progress
+            .iter()
+            .map(|(_, progress)| *progress)
+            .fold(0.0, f32::max);
+
+        progress
+            .into_iter()
+            .map(|(player, progress)| Standing {
+                player,
+                progress,
+                gap: leader - progress,
+            })
+            .collect()
+    }
+
+    /// The point along the route closest to `point`, extending the route if
+    /// it's somewhere new
+    fn locate(&mut self, point: [f32; 2]) -> usize {
+        let nearest = self
+            .route
+            .iter()
+            .enumerate()
+            .map(|(i, [x, y])| (i, (x - point[0]).hypot(y - point[1])))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match nearest {
+            Some((i, distance)) if distance <= ROUTE_REACH => {
+                // fill in the route behind whoever's out in front, but not
+                // for everyone close by
+                if distance > ROUTE_SPACING && i == self.route.len() - 1 {
+                    self.route.push(point);
+                    return i + 1;
+                }
+                i
+            }
+            _ => {
+                self.route.push(point);
+                self.route.len() - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, Marker, Progress};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn finds_the_players_rings() {
+        let image_data = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec-data/screens/race/test_1.jpg"
+        ));
+        let frame = image::load_from_memory(image_data).expect("failed to open image");
+
+        let markers = read(&frame);
+
+        assert_eq!(
+            markers,
+            vec![
+                Marker {
+                    player: 0,
+                    x: 224,
+                    y: 130
+                },
+                Marker {
+                    player: 1,
+                    x: 232,
+                    y: 75
+                },
+                Marker {
+                    player: 2,
+                    x: 150,
+                    y: 171
+                },
+                Marker {
+                    player: 3,
+                    x: 138,
+                    y: 184
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_laps_around_the_route() {
+        let mut progress = Progress::new();
+        let marker = |x, y| Marker { player: 0, x, y };
+
+        // around a square and back to the start
+        let mut points = vec![];
+        points.extend((0..10).map(|i| marker(i * 5, 0)));
+        points.extend((0..10).map(|i| marker(50, i * 5)));
+        points.extend((0..10).map(|i| marker(50 - i * 5, 50)));
+        points.extend((0..10).map(|i| marker(0, 50 - i * 5)));
+        points.push(marker(0, 0));
+        points.push(marker(10, 0));
+
+        let last = points
+            .iter()
+            .map(|marker| progress.update(&[*marker]))
+            .last()
+            .unwrap();
+
+        assert_eq!(last.len(), 1);
+        assert!(
+            last[0].progress > 1.0 && last[0].progress < 1.1,
+            "progress was {}",
+            last[0].progress
+        );
+    }
+}
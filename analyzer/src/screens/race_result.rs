@@ -1,9 +1,14 @@
+use super::loading::Loading;
 use super::Screen;
+use crate::characters::find_character;
 use crate::color::{
-    average_colors, lightness, mostly_blue, mostly_green, mostly_red, COLOR_THRESHOLD,
+    average_colors, light_text_cutoff, lightness, max_color_diff, mostly_blue, mostly_green,
+    mostly_red, player_color,
 };
+use crate::digits::{read_number, read_signed_number};
+use crate::frame::Frame;
+use crate::ocr::read_text;
 use crate::reference::Reference;
-use crate::util::is_splitscreen;
 use rayon::prelude::*;
 use serde::{ser::SerializeMap, Serialize, Serializer};
 
@@ -11,8 +16,55 @@ const SCOREBOARD_TOP_MARGIN: u32 = 50;
 const SCOREBOARD_PLAYER_HEIGHT: u32 = 48;
 const SCOREBOARD_PLAYER_MARGIN: u32 = 4;
 
+// CPU rows are drawn in white on a dark background, rather than in a player's
+// colour. The position number is always at the left of the row, and the
+// character icon next to it.
+const POSITION_CROP: [u32; 2] = [355, 4];
+const POSITION_SIZE: [u32; 2] = [40, 34];
+const CPU_MIN_WHITE: f32 = 0.15;
+const CPU_MIN_DARK: f32 = 0.15;
+const ICON_CROP: [u32; 2] = [420, 4];
+const ICON_SIZE: u32 = 40;
+// the name fills the space between the icon and the points
+const NAME_CROP: [u32; 2] = [465, 6];
+const NAME_WIDTH: u32 = 320;
+
+// The points for the race are shown as "+15" next to each racer's previous
+// total, before being added on
+const POINTS_CROP: [u32; 2] = [800, 5];
+const POINTS_WIDTH: u32 = 80;
+const TOTAL_CROP: [u32; 2] = [915, 5];
+const TOTAL_WIDTH: u32 = 60;
+const NUMBER_HEIGHT: u32 = 36;
+// numbers read any less clearly than this are more likely to be something
+// else in the row, like the course showing through it
+const MIN_CONFIDENCE: f32 = 0.5;
+const DARK_TEXT_CUTOFF: u16 = 130;
+
+// Online, the totals are everyone's VR (versus rating), and the points are how
+// much it went up or down by. Ratings are wider than any total can get, and
+// higher - 15 points a race for 48 races is only 720.
+const VR_CROP: [u32; 2] = [875, 5];
+const VR_WIDTH: u32 = 100;
+const MIN_VR: u32 = 1_000;
+// without the split lines to go by, most of the rows have to look like the
+// results before we believe it. The loading screen between races shows the
+// same table, which is where the ratings come in.
+const MIN_TABLE_ROWS: usize = 8;
+const MAX_ONLINE_PLAYERS: usize = 2;
+
 const POINTS_AWARDED: [u8; 12] = [15, 12, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1];
 
+/// Where the character icon is on the row at `index` (from 0), which is where
+/// the character references are cut from
+pub(crate) fn icon_region(index: u32) -> [u32; 4] {
+    let top =
+        SCOREBOARD_TOP_MARGIN + 2 + index * (SCOREBOARD_PLAYER_HEIGHT + SCOREBOARD_PLAYER_MARGIN);
+    let [x, y] = ICON_CROP;
+
+    [x, top + y, ICON_SIZE, ICON_SIZE]
+}
+
 fn player_vec_serializer<S: Serializer>(
     players: &Vec<Player>,
     serializer: S,
@@ -20,14 +72,7 @@ fn player_vec_serializer<S: Serializer>(
     let mut map = serializer.serialize_map(Some(players.len()))?;
 
     for player in players.iter() {
-        let name = match player.index {
-            0 => "player_one",
-            1 => "player_two",
-            2 => "player_three",
-            3 => "player_four",
-            _ => panic!("too many players! only four supported!"),
-        };
-
+        let name = super::player_name(player.index);
         map.serialize_entry(name, player)?;
     }
 
@@ -38,6 +83,24 @@ fn player_vec_serializer<S: Serializer>(
 pub struct RaceResult {
     #[serde(serialize_with = "player_vec_serializer", flatten)]
     players: Vec<Player>,
+
+    /// Every row on the scoreboard, CPUs included, in finishing order
+    racers: Vec<Racer>,
+
+    phase: Phase,
+
+    /// Online results show everyone's VR in place of their points
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    online: bool,
+}
+
+/// The scoreboard shows the points for the race first, then adds them on to
+/// everyone's totals
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Awarding,
+    Totals,
 }
 
 #[derive(Debug, PartialEq, Serialize, Clone)]
@@ -46,23 +109,82 @@ pub struct Player {
     index: u8,
     position: u8,
     points: u8,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct Racer {
+    position: u8,
+    points: u8,
+    is_human: bool,
+
+    #[serde(
+        serialize_with = "super::serialize_player",
+        skip_serializing_if = "Option::is_none"
+    )]
+    player: Option<u8>,
+
+    /// The name shown on the row - the Mii name for players, or the
+    /// character's name for CPUs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    /// The points shown on screen for this race, as they were read. These
+    /// should always match `points` - see `awarded_points_match`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    awarded_points: Option<u8>,
+
+    /// The running total shown on screen. Depending on the `phase`, this may
+    /// or may not include this race yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_points: Option<u16>,
+
+    /// The racer's VR, after this race, in online races
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vr: Option<u32>,
+
+    /// How much the racer's VR went up or down by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vr_change: Option<i32>,
+
+    /// The character on the row's icon, if it's one we have a reference for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    character: Option<&'static str>,
+}
+
+impl RaceResult {
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+}
+
+impl Racer {
+    /// Whether the points shown for this race are the ones every race gives
+    /// for the racer's place, if they could be read at all. They never change,
+    /// so if they don't match, the reading (or the row) is wrong.
+    pub fn awarded_points_match(&self) -> Option<bool> {
+        self.awarded_points.map(|points| points == self.points)
+    }
 }
 
 impl Reference for RaceResult {
-    fn compare(frame: &image::DynamicImage) -> bool {
-        if !is_splitscreen(frame) {
-            return false;
+    fn compare(frame: &Frame) -> bool {
+        if !frame.is_splitscreen() {
+            // online races aren't split, so there's only the table, and the
+            // ratings on it, to go by
+            return is_online_table(frame);
         }
         let width = frame.width();
-        let frame = frame.to_rgb16();
-        let pixel = frame.get_pixel(width / 2 - 1, 50);
+        let pixel = frame.rgb16_pixel(width / 2 - 1, 50);
 
-        let value = lightness(pixel);
+        let value = lightness(&pixel);
 
         return value > 12_850;
     }
 
-    fn process(frame: &image::DynamicImage) -> Option<Screen> {
+    fn process(frame: &Frame) -> Option<Screen> {
         let pixels = frame
             .crop_imm(320, 70, 1, 600)
             .to_rgb16()
@@ -74,53 +196,373 @@ impl Reference for RaceResult {
             return None;
         }
 
-        let mut players: Vec<_> = (0..12)
+        let mut racers: Vec<_> = (0..12)
             .par_bridge()
             .filter_map(|i| {
-                let offset = 2 + i * (SCOREBOARD_PLAYER_HEIGHT + SCOREBOARD_PLAYER_MARGIN);
-                let check_slice = frame.crop_imm(
-                    (frame.width() / 2) - 1,
-                    SCOREBOARD_TOP_MARGIN + offset,
-                    2,
-                    2,
-                );
-
-                let [average_red, average_green, average_blue] = average_colors(&check_slice);
+                let top = SCOREBOARD_TOP_MARGIN
+                    + 2
+                    + i * (SCOREBOARD_PLAYER_HEIGHT + SCOREBOARD_PLAYER_MARGIN);
+                let check_slice = frame.view((frame.width() / 2) - 1, top, 2, 2);
 
                 let position: u8 = i as u8 + 1;
-                let points = POINTS_AWARDED[i as usize];
-                if average_red > COLOR_THRESHOLD && average_green > COLOR_THRESHOLD {
-                    Some(Player {
-                        index: 0,
-                        position,
-                        points,
-                    })
-                } else if average_green > COLOR_THRESHOLD && average_blue > COLOR_THRESHOLD {
-                    Some(Player {
-                        index: 1,
-                        position,
-                        points,
-                    })
-                } else if average_green > COLOR_THRESHOLD {
-                    Some(Player {
-                        index: 3,
-                        position,
-                        points,
-                    })
-                } else if average_red > COLOR_THRESHOLD {
-                    Some(Player {
-                        index: 2,
-                        position,
-                        points,
-                    })
-                } else {
-                    None
+                let player = player_color(average_colors(&*check_slice));
+                if player.is_none() && !is_cpu_row(frame, top) {
+                    return None;
                 }
+
+                let [icon_x, icon_y] = ICON_CROP;
+                let icon = frame.crop_imm(icon_x, top + icon_y, ICON_SIZE, ICON_SIZE);
+
+                let crop =
+                    |[x, y]: [u32; 2], width: u32| frame.crop_imm(x, top + y, width, NUMBER_HEIGHT);
+                let is_human = player.is_some();
+
+                Some(Racer {
+                    position,
+                    points: POINTS_AWARDED[i as usize],
+                    is_human,
+                    player,
+                    name: read_text(&crop(NAME_CROP, NAME_WIDTH), is_human),
+                    awarded_points: read_row(
+                        &crop(POINTS_CROP, POINTS_WIDTH),
+                        is_human,
+                        read_digits,
+                    )
+                    .and_then(|n| u8::try_from(n).ok()),
+                    total_points: read_row(&crop(TOTAL_CROP, TOTAL_WIDTH), is_human, read_digits)
+                        .and_then(|n| u16::try_from(n).ok()),
+                    vr: read_row(&crop(VR_CROP, VR_WIDTH), is_human, read_digits),
+                    vr_change: read_row(
+                        &crop(POINTS_CROP, POINTS_WIDTH),
+                        is_human,
+                        read_signed_digits,
+                    ),
+                    character: find_character(&icon),
+                })
+            })
+            .collect();
+
+        racers.sort_unstable_by_key(|racer| racer.position);
+
+        let online = racers
+            .iter()
+            .any(|racer| racer.vr.is_some_and(|vr| vr >= MIN_VR));
+        for racer in racers.iter_mut() {
+            if online {
+                racer.awarded_points = None;
+                racer.total_points = None;
+            } else {
+                racer.vr = None;
+                racer.vr_change = None;
+            }
+        }
+
+        let mut players: Vec<_> = racers
+            .iter()
+            .filter_map(|racer| {
+                racer.player.map(|index| Player {
+                    index,
+                    position: racer.position,
+                    points: racer.points,
+                    name: racer.name.clone(),
+                })
             })
             .collect();
 
         players.sort_unstable_by(|a, b| a.index.cmp(&b.index));
 
-        Some(Screen::RaceResult(RaceResult { players }))
+        // once the points have been added on, the "+15"s disappear. a stray
+        // misread shouldn't be enough to flip it though
+        let awarded = racers
+            .iter()
+            .filter(|racer| racer.awarded_points.is_some())
+            .count();
+        let phase = if awarded * 2 >= racers.len() {
+            Phase::Awarding
+        } else {
+            Phase::Totals
+        };
+
+        Some(Screen::RaceResult(RaceResult {
+            players,
+            racers,
+            phase,
+            online,
+        }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        (0..12)
+            .flat_map(|i| {
+                let top = SCOREBOARD_TOP_MARGIN
+                    + 2
+                    + i * (SCOREBOARD_PLAYER_HEIGHT + SCOREBOARD_PLAYER_MARGIN);
+                let [position_x, position_y] = POSITION_CROP;
+                let [position_width, position_height] = POSITION_SIZE;
+                let [icon_x, icon_y] = ICON_CROP;
+                let row = |[x, y]: [u32; 2], width| [x, top + y, width, NUMBER_HEIGHT];
+
+                [
+                    [
+                        position_x,
+                        top + position_y,
+                        position_width,
+                        position_height,
+                    ],
+                    [icon_x, top + icon_y, ICON_SIZE, ICON_SIZE],
+                    row(NAME_CROP, NAME_WIDTH),
+                    row(POINTS_CROP, POINTS_WIDTH),
+                    row(TOTAL_CROP, TOTAL_WIDTH),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Reads the text in `section` with `reader`, which is handed white text on a
+/// dark background. Players' rows usually have dark text, but the row being
+/// animated (and every CPU row) has white text.
+pub(super) fn read_row<T>(
+    section: &image::DynamicImage,
+    is_human: bool,
+    reader: impl Fn(&image::DynamicImage) -> Option<T>,
+) -> Option<T> {
+    let dark = is_human.then(|| {
+        let mut section = section.clone();
+        max_color_diff(&mut section, DARK_TEXT_CUTOFF);
+        section.invert();
+        reader(&section)
+    });
+
+    dark.flatten().or_else(|| {
+        let mut section = section.clone();
+        let cutoff = light_text_cutoff(&section);
+        max_color_diff(&mut section, cutoff);
+        reader(&section)
+    })
+}
+
+pub(super) fn read_digits(section: &image::DynamicImage) -> Option<u32> {
+    // `read_number` wants dark digits on a light background
+    let mut section = section.clone();
+    section.invert();
+    read_number(&section)
+        .filter(|number| number.confidence >= MIN_CONFIDENCE)
+        .map(|number| number.value)
+}
+
+fn read_signed_digits(section: &image::DynamicImage) -> Option<i32> {
+    let mut section = section.clone();
+    section.invert();
+    read_signed_number(&section)
+        .filter(|number| number.confidence >= MIN_CONFIDENCE)
+        .map(|number| number.value)
+}
+
+/// Whether this is the online results table - most of the rows look like
+/// rows of the results, either in a player's colour or with a position drawn
+/// in white on a dark background, and someone has a rating. The loading screen
+/// shows the table too, so that's ruled out first.
+///
+/// Only two players can share a console online, so any more coloured rows
+/// than that are something else that happens to be brightly coloured.
+fn is_online_table(frame: &Frame) -> bool {
+    // most frames aren't a table at all, so give up as soon as too many rows
+    // are missing to make one, before anything more costly
+    let mut rows = Vec::with_capacity(12);
+    let mut missing = 0;
+
+    for i in 0..12 {
+        let top =
+            SCOREBOARD_TOP_MARGIN + 2 + i * (SCOREBOARD_PLAYER_HEIGHT + SCOREBOARD_PLAYER_MARGIN);
+        let check_slice = frame.view((frame.width() / 2) - 1, top, 2, 2);
+        let player = player_color(average_colors(&*check_slice));
+
+        // a coloured row can still be dark, for players who aren't ours
+        if is_cpu_row(frame, top) {
+            rows.push((top, false));
+        } else if player.is_some() {
+            rows.push((top, true));
+        } else {
+            missing += 1;
+            if missing > 12 - MIN_TABLE_ROWS {
+                return false;
+            }
+        }
+    }
+
+    let coloured = rows.iter().filter(|(_, is_human)| *is_human).count();
+    let dark = rows.len() - coloured;
+
+    dark + coloured.min(MAX_ONLINE_PLAYERS) >= MIN_TABLE_ROWS
+        && !Loading::compare(frame)
+        && rows.iter().any(|(top, is_human)| {
+            let [x, y] = VR_CROP;
+            let section = frame.crop_imm(x, top + y, VR_WIDTH, NUMBER_HEIGHT);
+            read_row(&section, *is_human, read_digits).is_some_and(|vr| vr >= MIN_VR)
+        })
+}
+
+/// CPU rows don't have a colour to go by, but their position number is drawn
+/// in white on a dark row. Rows that haven't slid in yet show the course behind
+/// them, which is neither.
+fn is_cpu_row(frame: &image::DynamicImage, top: u32) -> bool {
+    let [x, y] = POSITION_CROP;
+    let [width, height] = POSITION_SIZE;
+    let section = frame.crop_imm(x, top + y, width, height).to_luma8();
+
+    let total = (width * height) as f32;
+    let white = section.pixels().filter(|p| p.0[0] > 200).count() as f32 / total;
+    let dark = section.pixels().filter(|p| p.0[0] < 70).count() as f32 / total;
+
+    white > CPU_MIN_WHITE && dark > CPU_MIN_DARK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Phase, RaceResult};
+    use crate::frame::Frame;
+    use crate::reference::Reference;
+    use crate::screens::Screen;
+    use pretty_assertions::assert_eq;
+
+    macro_rules! test_frame {
+        ($file:literal) => {{
+            let image_data = include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/spec-data/screens/race_result/",
+                $file,
+                ".jpg"
+            ));
+            let frame = image::load_from_memory(image_data).expect("failed to open image");
+            let Some(Screen::RaceResult(result)) = RaceResult::process(&Frame::new(&frame)) else {
+                panic!("didn't read the race results");
+            };
+            result
+        }};
+    }
+
+    /// Not every number can be read on every frame, but the ones that are
+    /// read have to be right, and there have to be at least `min_read` totals
+    fn assert_points(result: &RaceResult, totals: &[u16], min_read: usize) {
+        let read = result
+            .racers
+            .iter()
+            .filter(|racer| racer.total_points.is_some())
+            .count();
+        assert!(read >= min_read, "only read {read} totals");
+
+        for racer in &result.racers {
+            let index = racer.position as usize - 1;
+            assert_ne!(
+                racer.awarded_points_match(),
+                Some(false),
+                "row {} read {:?}",
+                racer.position,
+                racer.awarded_points
+            );
+            if let Some(total) = racer.total_points {
+                assert_eq!(total, totals[index], "row {}", racer.position);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_every_row() {
+        let result = test_frame!("every_row");
+
+        let rows: Vec<_> = result
+            .racers
+            .iter()
+            .map(|racer| (racer.position, racer.player, racer.character))
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                (1, Some(3), Some("Birdo (Light Blue)")),
+                (2, Some(0), Some("Baby Luigi")),
+                (3, None, Some("Baby Peach")),
+                (4, Some(1), Some("Roy")),
+                (5, None, Some("Inkling Girl")),
+                (6, None, Some("Donkey Kong")),
+                (7, Some(2), Some("Koopa Troopa")),
+                (8, None, Some("Pink Gold Peach")),
+                (9, None, Some("Luigi")),
+                (10, None, Some("Wendy")),
+                (11, None, Some("Baby Rosalina")),
+                (12, None, Some("Wario")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_points_being_awarded() {
+        let result = test_frame!("every_row");
+
+        assert_eq!(result.phase, Phase::Awarding);
+        assert_points(&result, &[35, 27, 27, 40, 17, 27, 19, 21, 13, 8, 6, 6], 11);
+    }
+
+    #[test]
+    fn reads_totals() {
+        let result = test_frame!("totals");
+
+        assert_eq!(result.phase, Phase::Totals);
+        assert_points(&result, &[64, 62, 47, 34, 33, 41, 45, 16, 21, 29, 10, 8], 9);
+    }
+
+    #[test]
+    fn reads_names() {
+        // the glyph references were cut from `every_row`, so this checks them
+        // against a frame they didn't come from
+        let result = test_frame!("totals");
+
+        let names: Vec<_> = result
+            .racers
+            .iter()
+            .map(|racer| racer.name.as_deref())
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                Some("Roy"),
+                Some("Birdo (Light Blue)"),
+                Some("Baby Peach"),
+                Some("Koopa Troopa"),
+                Some("Inkling Girl"),
+                Some("Donkey Kong"),
+                Some("Baby Luigi"),
+                Some("Wendy"),
+                // the course shows through the row too brightly to read it
+                None,
+                Some("Pink Gold Peach"),
+                Some("Baby Rosalina"),
+                Some("Wario"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_rows_sliding_in() {
+        let result = test_frame!("sliding_in");
+
+        let awarded: Vec<_> = result
+            .racers
+            .iter()
+            .map(|racer| (racer.awarded_points, racer.awarded_points_match()))
+            .collect();
+
+        // the third row is still sliding in, so only the "+1" of its "+10" is
+        // where the points should be, and the check has to catch that
+        assert_eq!(
+            awarded,
+            vec![
+                (Some(15), Some(true)),
+                (Some(12), Some(true)),
+                (Some(1), Some(false)),
+            ]
+        );
     }
 }
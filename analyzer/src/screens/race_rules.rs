@@ -0,0 +1,203 @@
+use super::Screen;
+use crate::frame::Frame;
+use crate::ocr::read_long_text;
+use crate::reference::Reference;
+use serde::Serialize;
+
+// Each rule is a row on the VS rules screen, with the current value drawn in
+// dark text on the right hand side of the row
+const CLASS_CROP: [u32; 2] = [640, 168];
+const TEAMS_CROP: [u32; 2] = [640, 226];
+const ITEMS_CROP: [u32; 2] = [640, 284];
+const COM_CROP: [u32; 2] = [640, 342];
+const COM_VEHICLES_CROP: [u32; 2] = [640, 400];
+const COURSES_CROP: [u32; 2] = [640, 458];
+const RACES_CROP: [u32; 2] = [640, 516];
+const VALUE_WIDTH: u32 = 360;
+const VALUE_HEIGHT: u32 = 30;
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub enum Class {
+    #[serde(rename = "50cc")]
+    Cc50,
+    #[serde(rename = "100cc")]
+    Cc100,
+    #[serde(rename = "150cc")]
+    Cc150,
+    #[serde(rename = "200cc")]
+    Cc200,
+    #[serde(rename = "mirror")]
+    Mirror,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Teams {
+    NoTeams,
+    Teams,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Items {
+    Normal,
+    Frantic,
+    Custom,
+    ShellsOnly,
+    BananasOnly,
+    MushroomsOnly,
+    BobOmbsOnly,
+    NoItems,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Com {
+    Easy,
+    Normal,
+    Hard,
+    NoCom,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum ComVehicles {
+    All,
+    KartsOnly,
+    BikesOnly,
+    AtvsOnly,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Courses {
+    Choose,
+    InOrder,
+    Random,
+}
+
+const CLASS_OPTIONS: [(Class, &str); 5] = [
+    (Class::Cc50, "50cc"),
+    (Class::Cc100, "100cc"),
+    (Class::Cc150, "150cc"),
+    (Class::Cc200, "200cc"),
+    (Class::Mirror, "Mirror"),
+];
+
+const TEAMS_OPTIONS: [(Teams, &str); 2] = [(Teams::NoTeams, "No Teams"), (Teams::Teams, "Teams")];
+
+const ITEMS_OPTIONS: [(Items, &str); 8] = [
+    (Items::Normal, "Normal Items"),
+    (Items::Frantic, "Frantic Items"),
+    (Items::Custom, "Custom Items"),
+    (Items::ShellsOnly, "Shells Only"),
+    (Items::BananasOnly, "Bananas Only"),
+    (Items::MushroomsOnly, "Mushrooms Only"),
+    (Items::BobOmbsOnly, "Bob-ombs Only"),
+    (Items::NoItems, "No Items"),
+];
+
+const COM_OPTIONS: [(Com, &str); 4] = [
+    (Com::Easy, "Easy"),
+    (Com::Normal, "Normal"),
+    (Com::Hard, "Hard"),
+    (Com::NoCom, "No COM"),
+];
+
+const COM_VEHICLES_OPTIONS: [(ComVehicles, &str); 4] = [
+    (ComVehicles::All, "All Vehicles"),
+    (ComVehicles::KartsOnly, "Karts Only"),
+    (ComVehicles::BikesOnly, "Bikes Only"),
+    (ComVehicles::AtvsOnly, "ATVs Only"),
+];
+
+const COURSES_OPTIONS: [(Courses, &str); 3] = [
+    (Courses::Choose, "Choose"),
+    (Courses::InOrder, "In Order"),
+    (Courses::Random, "Random"),
+];
+
+// the most races a VS match can be set to
+const MAX_RACES: u8 = 48;
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct RaceRules {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<Class>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teams: Option<Teams>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Items>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub com: Option<Com>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub com_vehicles: Option<ComVehicles>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub courses: Option<Courses>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub races: Option<u8>,
+}
+
+impl Reference for RaceRules {
+    fn compare(frame: &Frame) -> bool {
+        if frame.is_splitscreen() {
+            return false;
+        }
+
+        // the class and items rows are always there, whatever else is set
+        read_row(frame, CLASS_CROP, &CLASS_OPTIONS).is_some()
+            && read_row(frame, ITEMS_CROP, &ITEMS_OPTIONS).is_some()
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let race_counts: Vec<(u8, String)> = (1..=MAX_RACES).map(|n| (n, n.to_string())).collect();
+        let race_counts: Vec<(u8, &str)> = race_counts
+            .iter()
+            .map(|(n, text)| (*n, text.as_str()))
+            .collect();
+
+        Some(Screen::RaceRules(RaceRules {
+            class: read_row(frame, CLASS_CROP, &CLASS_OPTIONS),
+            teams: read_row(frame, TEAMS_CROP, &TEAMS_OPTIONS),
+            items: read_row(frame, ITEMS_CROP, &ITEMS_OPTIONS),
+            com: read_row(frame, COM_CROP, &COM_OPTIONS),
+            com_vehicles: read_row(frame, COM_VEHICLES_CROP, &COM_VEHICLES_OPTIONS),
+            courses: read_row(frame, COURSES_CROP, &COURSES_OPTIONS),
+            races: read_row(frame, RACES_CROP, &race_counts),
+        }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        [
+            CLASS_CROP,
+            TEAMS_CROP,
+            ITEMS_CROP,
+            COM_CROP,
+            COM_VEHICLES_CROP,
+            COURSES_CROP,
+            RACES_CROP,
+        ]
+        .iter()
+        .map(|[x, y]| [*x, *y, VALUE_WIDTH, VALUE_HEIGHT])
+        .collect()
+    }
+}
+
+fn read_row<T: Copy>(frame: &Frame, [x, y]: [u32; 2], options: &[(T, &str)]) -> Option<T> {
+    let value = frame.crop_imm(x, y, VALUE_WIDTH, VALUE_HEIGHT);
+    // values like "50cc" and race counts don't start with a capital, so they
+    // can't go through `read_text`. anything that isn't made out is read as a
+    // `?`, which no option has in it
+    let text = read_long_text(&value, true)?;
+
+    options
+        .iter()
+        .find(|(_, label)| *label == text)
+        .map(|(value, _)| *value)
+}
@@ -0,0 +1,52 @@
+use super::race::{self, Race};
+use super::Screen;
+use crate::color::{average_colors, mostly_yellow};
+use crate::frame::Frame;
+use crate::reference::Reference;
+use serde::Serialize;
+
+// Once everyone's over the line, and in the highlight reel, the race is played
+// back in the same split screen, so the divider alone can't tell it apart.
+// What a replay doesn't have is anyone's HUD - there's no lap counter in any
+// of the quarters, which a race always has. The only other time that happens
+// is while the "1st Race" banner is across the bottom, before the start.
+const BANNER_CROPS: [[u32; 2]; 2] = [[160, 600], [1090, 600]];
+const BANNER_SIZE: [u32; 2] = [30, 10];
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct Replay {}
+
+impl Reference for Replay {
+    fn compare(frame: &Frame) -> bool {
+        Race::compare(frame)
+            && !(0..4).any(|p| race::check_player_exists(frame, &p))
+            && !has_banner(frame)
+    }
+
+    fn process(_frame: &Frame) -> Option<Screen> {
+        Some(Screen::Replay(Replay {}))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        let [width, height] = race::LAP_FLAG_SIZE;
+        let [banner_width, banner_height] = BANNER_SIZE;
+
+        race::LAP_FLAG_CROP
+            .iter()
+            .map(|[x, y]| [*x, *y, width, height])
+            .chain(
+                BANNER_CROPS
+                    .iter()
+                    .map(|[x, y]| [*x, *y, banner_width, banner_height]),
+            )
+            .collect()
+    }
+}
+
+fn has_banner(frame: &Frame) -> bool {
+    let [width, height] = BANNER_SIZE;
+
+    BANNER_CROPS
+        .iter()
+        .all(|[x, y]| mostly_yellow(average_colors(&*frame.view(*x, *y, width, height))))
+}
@@ -1,6 +1,8 @@
 use super::super::hasher;
 use super::super::reference::Reference;
+use crate::frame::Frame;
 use crate::load_reference_hash;
+use crate::thresholds::{threshold, Sample};
 use lazy_static::lazy_static;
 use serde::Serialize;
 
@@ -9,19 +11,42 @@ lazy_static! {
         load_reference_hash!("select_character.jpg");
 }
 
+pub(crate) fn load_references() {
+    lazy_static::initialize(&REFERENCE_HASH);
+}
+
 #[derive(Debug, PartialEq, Serialize, Clone)]
 pub struct SelectCharacter {}
 
 impl Reference for SelectCharacter {
-    fn process(_frame: &image::DynamicImage) -> Option<super::Screen> {
+    fn process(_frame: &Frame) -> Option<super::Screen> {
         Some(super::Screen::SelectCharacter(SelectCharacter {}))
     }
-    fn compare(frame: &image::DynamicImage) -> bool {
-        let crop = frame.crop_imm(735, 435, 40, 35);
+    fn compare(frame: &Frame) -> bool {
+        distance(frame) <= threshold("select_character")
+    }
 
-        let check_hash = hasher::hash_image(crop);
-        let delta = REFERENCE_HASH.dist(&check_hash);
+    fn regions() -> Vec<[u32; 4]> {
+        vec![CROP]
+    }
+}
+
+const CROP: [u32; 4] = [735, 435, 40, 35];
+
+fn distance(frame: &image::DynamicImage) -> u32 {
+    let [x, y, width, height] = CROP;
+    let crop = frame.crop_imm(x, y, width, height);
+
+    let check_hash = hasher::hash_image(crop);
+    REFERENCE_HASH.dist(&check_hash)
+}
 
-        return delta <= 10;
+pub(crate) fn sample(frame: &image::DynamicImage) -> Sample {
+    Sample {
+        reference: "select_character".into(),
+        region: CROP,
+        player: None,
+        label: None,
+        distance: distance(frame),
     }
 }
@@ -0,0 +1,273 @@
+use super::Screen;
+use crate::color::{average_colors, lightness, player_color};
+use crate::frame::Frame;
+use crate::reference::Reference;
+use crate::screens::intro::VARIANT_GROUPS;
+use lazy_static::lazy_static;
+use serde::{Serialize, Serializer};
+
+pub struct Cup {
+    pub name: &'static str,
+    pub courses: Vec<&'static str>,
+}
+
+// The cups in the order they're laid out on the grid. Which courses are in
+// each one comes from the intro references, so a course picked here can be
+// matched up with the intro screen that follows it
+const CUP_NAMES: [&str; 24] = [
+    "Mushroom Cup",
+    "Flower Cup",
+    "Star Cup",
+    "Special Cup",
+    "Egg Cup",
+    "Crossing Cup",
+    "Shell Cup",
+    "Banana Cup",
+    "Leaf Cup",
+    "Lightning Cup",
+    "Triforce Cup",
+    "Bell Cup",
+    "Golden Dash Cup",
+    "Lucky Cat Cup",
+    "Turnip Cup",
+    "Propeller Cup",
+    "Rock Cup",
+    "Moon Cup",
+    "Fruit Cup",
+    "Boomerang Cup",
+    "Feather Cup",
+    "Cherry Cup",
+    "Acorn Cup",
+    "Spiny Cup",
+];
+
+lazy_static! {
+    pub static ref CUPS: Vec<Cup> = CUP_NAMES
+        .iter()
+        .map(|name| {
+            let mut courses: Vec<_> = VARIANT_GROUPS
+                .iter()
+                .flat_map(|group| group.tracks.iter())
+                .filter_map(|track| match track.cup {
+                    Some((cup, slot)) if cup == *name => Some((slot, track.name)),
+                    _ => None,
+                })
+                .collect();
+            courses.sort_unstable();
+
+            Cup {
+                name,
+                courses: courses.into_iter().map(|(_, course)| course).collect(),
+            }
+        })
+        .collect();
+}
+
+// The cups are laid out in a grid of six across, in the same order as
+// `CUP_NAMES`.
+// Each cell has a border that takes on the colour of the player moving the
+// cursor around.
+const CUP_COLUMNS: u32 = 6;
+const CUP_GRID_ORIGIN: [u32; 2] = [46, 112];
+const CUP_CELL_SIZE: u32 = 96;
+const CUP_BORDER_HEIGHT: u32 = 4;
+const PANEL_LIGHTNESS: u16 = 50_000;
+
+// Once a cup is chosen, the cursor moves to the list of its courses on the
+// right. Confirming a course flashes its row white.
+const COURSE_ROW_CROP: [[u32; 2]; 4] = [[730, 196], [730, 266], [730, 336], [730, 406]];
+const COURSE_ROW_WIDTH: u32 = 470;
+const COURSE_ROW_HEIGHT: u32 = 4;
+const PICKED_LIGHTNESS: u16 = 60_000;
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct SelectCourse {
+    pub cup: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub course: Option<&'static str>,
+
+    pub picked: bool,
+
+    #[serde(serialize_with = "player_serializer")]
+    pub player: u8,
+}
+
+fn player_serializer<S: Serializer>(index: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(super::player_name(*index))
+}
+
+impl Reference for SelectCourse {
+    fn compare(frame: &Frame) -> bool {
+        if frame.is_splitscreen() {
+            return false;
+        }
+
+        get_cup(frame).is_some()
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let (cup, player) = get_cup(frame)?;
+
+        let (course, picked) = match get_course(frame) {
+            Some((index, picked)) => (cup.courses.get(index).copied(), picked),
+            None => (None, false),
+        };
+
+        Some(Screen::SelectCourse(SelectCourse {
+            cup: cup.name,
+            course,
+            picked,
+            player,
+        }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        let [x, y] = CUP_GRID_ORIGIN;
+        let cups = (0..CUP_NAMES.len() as u32).map(|i| {
+            let column = i % CUP_COLUMNS;
+            let row = i / CUP_COLUMNS;
+
+            [
+                x + column * CUP_CELL_SIZE,
+                y + row * CUP_CELL_SIZE,
+                CUP_CELL_SIZE,
+                CUP_BORDER_HEIGHT,
+            ]
+        });
+        let courses = COURSE_ROW_CROP
+            .iter()
+            .map(|[x, y]| [*x, *y, COURSE_ROW_WIDTH, COURSE_ROW_HEIGHT]);
+
+        cups.chain(courses).collect()
+    }
+}
+
+/// Finds the cup under the cursor, and the player the cursor belongs to
+fn get_cup(frame: &Frame) -> Option<(&'static Cup, u8)> {
+    let [x, y] = CUP_GRID_ORIGIN;
+    let borders: Vec<[u16; 3]> = (0..CUP_NAMES.len() as u32)
+        .map(|i| {
+            let column = i % CUP_COLUMNS;
+            let row = i / CUP_COLUMNS;
+            let border = frame.view(
+                x + column * CUP_CELL_SIZE,
+                y + row * CUP_CELL_SIZE,
+                CUP_CELL_SIZE,
+                CUP_BORDER_HEIGHT,
+            );
+
+            average_colors(&*border)
+        })
+        .collect();
+
+    // the cups without the cursor sit on light panels. if most of them aren't
+    // light, this isn't the cup grid
+    let is_panel = |color: &[u16; 3]| lightness(&image::Rgb(*color)) > PANEL_LIGHTNESS;
+    let panels = borders.iter().filter(|color| is_panel(color)).count();
+    if panels < CUP_NAMES.len() - 2 {
+        return None;
+    }
+
+    let mut highlighted = borders
+        .iter()
+        .zip(CUPS.iter())
+        .filter(|(color, _)| !is_panel(color))
+        .filter_map(|(color, cup)| player_color(*color).map(|player| (cup, player)));
+
+    // there is only one cursor on this screen - if more than one cup looks
+    // highlighted, it isn't the cup grid
+    match (highlighted.next(), highlighted.next()) {
+        (Some(cup), None) => Some(cup),
+        _ => None,
+    }
+}
+
+/// Finds the highlighted course in the chosen cup, and whether it's been
+/// confirmed
+fn get_course(frame: &Frame) -> Option<(usize, bool)> {
+    COURSE_ROW_CROP.iter().enumerate().find_map(|(i, [x, y])| {
+        let row = frame.view(*x, *y, COURSE_ROW_WIDTH, COURSE_ROW_HEIGHT);
+        let [r, g, b] = average_colors(&*row);
+
+        if lightness(&image::Rgb([r, g, b])) > PICKED_LIGHTNESS {
+            Some((i, true))
+        } else if player_color([r, g, b]).is_some() {
+            Some((i, false))
+        } else {
+            None
+        }
+    })
+}
+
+/// The course a name read off the screen is closest to. Names are compared
+/// ignoring case and punctuation, and only a few characters are allowed to be
+/// wrong, so a short misread name doesn't match whatever course happens to be
+/// closest.
+pub(crate) fn find_course(text: &str) -> Option<&'static str> {
+    let text = simplify(text);
+    if text.is_empty() {
+        return None;
+    }
+
+    CUPS.iter()
+        .flat_map(|cup| cup.courses.iter())
+        .map(|course| (*course, edit_distance(&text, &simplify(course))))
+        .filter(|(course, distance)| distance * MAX_MISREAD <= simplify(course).len())
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(course, _)| course)
+}
+
+// one character in this many can be misread before a name stops matching
+const MAX_MISREAD: usize = 4;
+
+fn simplify(text: &str) -> Vec<char> {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// How many characters have to be added, removed or changed to turn `a` into
+/// `b`
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let change = previous[j] + usize::from(ca != cb);
+            current.push(change.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_course, CUPS};
+
+    #[test]
+    fn every_cup_has_four_courses() {
+        for cup in CUPS.iter() {
+            assert_eq!(cup.courses.len(), 4, "{} has {:?}", cup.name, cup.courses);
+        }
+    }
+
+    #[test]
+    fn finds_courses_from_misread_names() {
+        assert_eq!(
+            find_course("Mario Kart Stadium"),
+            Some("Mario Kart Stadium")
+        );
+        assert_eq!(find_course("Toad Harb?r"), Some("Toad Harbor"));
+        assert_eq!(
+            find_course("Rainbow Road ?N64?"),
+            Some("Rainbow Road (N64)")
+        );
+        assert_eq!(find_course("Random"), None);
+        assert_eq!(find_course("??"), None);
+    }
+}
@@ -0,0 +1,69 @@
+use super::Screen;
+use crate::frame::Frame;
+use crate::reference::Reference;
+use crate::time::{read_time, RaceTime, TimeLayout};
+use serde::Serialize;
+
+// The time trial HUD is the single player layout - the running time sits in the
+// top right, and each lap split gets added underneath it as the lap finishes
+const TIMER_CROP: [u32; 2] = [1045, 28];
+const LAP_CROP: [[u32; 2]; 3] = [[1091, 68], [1091, 94], [1091, 120]];
+
+const TIMER_LAYOUT: TimeLayout = TimeLayout {
+    digit_width: 19,
+    digit_height: 30,
+    offsets: [0, 30, 49, 79, 98, 117],
+};
+
+pub(crate) const LAP_LAYOUT: TimeLayout = TimeLayout {
+    digit_width: 13,
+    digit_height: 20,
+    offsets: [0, 20, 33, 53, 66, 79],
+};
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct TimeTrial {
+    pub time: RaceTime,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub laps: Vec<RaceTime>,
+}
+
+impl Reference for TimeTrial {
+    fn compare(frame: &Frame) -> bool {
+        if frame.is_splitscreen() {
+            return false;
+        }
+
+        read_time(frame, TIMER_CROP, &TIMER_LAYOUT).is_some()
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let time = read_time(frame, TIMER_CROP, &TIMER_LAYOUT)?;
+
+        Some(Screen::TimeTrial(TimeTrial {
+            time,
+            laps: get_laps(frame),
+        }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        let [x, y] = TIMER_CROP;
+        let laps = LAP_CROP
+            .iter()
+            .map(|[x, y]| [*x, *y, LAP_LAYOUT.width(), LAP_LAYOUT.digit_height]);
+
+        std::iter::once([x, y, TIMER_LAYOUT.width(), TIMER_LAYOUT.digit_height])
+            .chain(laps)
+            .collect()
+    }
+}
+
+/// Reads the lap splits listed under the timer. Laps that haven't been
+/// completed yet aren't shown, so we stop at the first one we can't read.
+fn get_laps(frame: &image::DynamicImage) -> Vec<RaceTime> {
+    LAP_CROP
+        .iter()
+        .map_while(|crop| read_time(frame, *crop, &LAP_LAYOUT))
+        .collect()
+}
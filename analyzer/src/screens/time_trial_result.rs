@@ -0,0 +1,81 @@
+use super::Screen;
+use crate::color::{average_colors, mostly_yellow};
+use crate::frame::Frame;
+use crate::reference::Reference;
+use crate::time::{read_time, RaceTime, TimeLayout};
+use serde::Serialize;
+
+// Once the last lap is done, the final time is shown in the middle of the
+// screen with the splits listed underneath it
+const FINAL_TIME_CROP: [u32; 2] = [700, 296];
+const LAP_CROP: [[u32; 2]; 3] = [[766, 360], [766, 390], [766, 420]];
+const NEW_RECORD_CROP: [u32; 4] = [700, 250, 216, 30];
+
+const FINAL_TIME_LAYOUT: TimeLayout = TimeLayout {
+    digit_width: 28,
+    digit_height: 44,
+    offsets: [0, 44, 72, 116, 144, 172],
+};
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct TimeTrialResult {
+    pub time: RaceTime,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub laps: Vec<RaceTime>,
+
+    pub new_record: bool,
+}
+
+impl Reference for TimeTrialResult {
+    fn compare(frame: &Frame) -> bool {
+        if frame.is_splitscreen() {
+            return false;
+        }
+
+        read_time(frame, FINAL_TIME_CROP, &FINAL_TIME_LAYOUT).is_some()
+    }
+
+    fn process(frame: &Frame) -> Option<Screen> {
+        let time = read_time(frame, FINAL_TIME_CROP, &FINAL_TIME_LAYOUT)?;
+
+        let laps = LAP_CROP
+            .iter()
+            .map_while(|crop| read_time(frame, *crop, &super::time_trial::LAP_LAYOUT))
+            .collect();
+
+        Some(Screen::TimeTrialResult(TimeTrialResult {
+            time,
+            laps,
+            new_record: check_new_record(frame),
+        }))
+    }
+
+    fn regions() -> Vec<[u32; 4]> {
+        let [x, y] = FINAL_TIME_CROP;
+        let lap_layout = &super::time_trial::LAP_LAYOUT;
+        let laps = LAP_CROP
+            .iter()
+            .map(|[x, y]| [*x, *y, lap_layout.width(), lap_layout.digit_height]);
+
+        [
+            [
+                x,
+                y,
+                FINAL_TIME_LAYOUT.width(),
+                FINAL_TIME_LAYOUT.digit_height,
+            ],
+            NEW_RECORD_CROP,
+        ]
+        .into_iter()
+        .chain(laps)
+        .collect()
+    }
+}
+
+fn check_new_record(frame: &Frame) -> bool {
+    let [x, y, width, height] = NEW_RECORD_CROP;
+    let banner = frame.view(x, y, width, height);
+
+    mostly_yellow(average_colors(&*banner))
+}
@@ -0,0 +1,435 @@
+use crate::screens::pause::PauseOption;
+use crate::screens::race::alerts::{Alert, Alerts};
+use crate::screens::race::events::{RaceIncident, Tracker};
+use crate::screens::race::Race;
+use crate::screens::Screen;
+use crate::time::RaceTime;
+use serde::Serialize;
+
+/// How a race came to an end.
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum RaceOutcome {
+    /// The results screen was shown
+    Finished,
+    /// Someone restarted or quit the race from the pause menu
+    Aborted,
+    /// The race frames stopped, but we never saw why
+    Incomplete,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone)]
+#[serde(untagged)]
+pub enum SessionEvent {
+    RaceStarted,
+    RaceEnded {
+        outcome: RaceOutcome,
+    },
+    PlayerFinished {
+        #[serde(serialize_with = "crate::screens::serialize_index")]
+        player: u8,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        position: Option<u8>,
+
+        /// Only known in time trials, where the final time is on the results
+        #[serde(skip_serializing_if = "Option::is_none")]
+        time: Option<RaceTime>,
+    },
+    Incident(RaceIncident),
+    /// Something everyone watching should know about right away
+    Alert(Alert),
+}
+
+impl SessionEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            SessionEvent::RaceStarted => "race_started",
+            SessionEvent::RaceEnded { .. } => "race_ended",
+            SessionEvent::PlayerFinished { .. } => "player_finished",
+            SessionEvent::Incident(incident) => incident.incident.event_type(),
+            SessionEvent::Alert(alert) => alert.event_type(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum State {
+    Idle,
+    Racing {
+        paused: Option<PauseOption>,
+    },
+    /// Restart was picked from the pause menu and the race has gone away, so
+    /// the next race frames are a new race
+    Restarting,
+}
+
+/// Follows screens from one frame to the next to work out where races start
+/// and end. `analyze` only ever looks at a single frame - this is where
+/// anything that needs history lives.
+#[derive(Debug)]
+pub struct Session {
+    state: State,
+    tracker: Tracker,
+    alerts: Alerts,
+    finished: [bool; 4],
+    /// Whether we've come from the online lobby, and not left it for the
+    /// offline menus since
+    online: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            state: State::Idle,
+            tracker: Tracker::new(),
+            alerts: Alerts::new(),
+            finished: [false; 4],
+            online: false,
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    pub fn update(&mut self, screen: &Screen) -> Vec<SessionEvent> {
+        match screen {
+            Screen::OnlineLobby(_) | Screen::CourseVote(_) => self.online = true,
+            Screen::RaceResult(result) if result.is_online() => self.online = true,
+            // online play never goes back through these
+            Screen::MainMenu(_) | Screen::SelectCourse(_) | Screen::RaceRules(_) => {
+                self.online = false
+            }
+            _ => {}
+        }
+
+        let was_racing = self.state != State::Idle;
+        let mut events = self.update_state(screen);
+
+        // time trials only show that they're over on the results, after the
+        // race has ended, which is also where the final time is
+        if let (true, Screen::TimeTrialResult(result)) = (was_racing, screen) {
+            events.insert(
+                0,
+                SessionEvent::PlayerFinished {
+                    player: 0,
+                    position: None,
+                    time: Some(result.time),
+                },
+            );
+        }
+
+        if let State::Idle = self.state {
+            return events;
+        }
+
+        if events.contains(&SessionEvent::RaceStarted) {
+            self.tracker = Tracker::new();
+            self.alerts = Alerts::new();
+            self.finished = [false; 4];
+        }
+
+        if let Screen::Race(race) = screen {
+            for player in race.players.iter().filter(|p| p.is_finished()) {
+                let finished = &mut self.finished[player.index() as usize];
+                if !*finished {
+                    *finished = true;
+                    events.push(SessionEvent::PlayerFinished {
+                        player: player.index(),
+                        position: player.position(),
+                        time: None,
+                    });
+                }
+            }
+
+            let incidents = self.tracker.update(race);
+            let alerts = self.alerts.update(race, &incidents);
+
+            events.extend(alerts.into_iter().map(SessionEvent::Alert));
+            events.extend(incidents.into_iter().map(SessionEvent::Incident));
+        }
+
+        events
+    }
+
+    fn update_state(&mut self, screen: &Screen) -> Vec<SessionEvent> {
+        match (self.state, screen) {
+            // the race going away with Restart picked is the restart itself,
+            // rather than just hovering over it
+            (
+                State::Racing {
+                    paused: Some(PauseOption::Restart),
+                },
+                Screen::Unknown | Screen::Loading(_),
+            ) => {
+                self.state = State::Restarting;
+                vec![]
+            }
+
+            // blank and transition frames don't tell us anything, and neither
+            // do replays of the race
+            (_, Screen::Unknown | Screen::Replay(_)) => vec![],
+
+            (State::Idle, Screen::Race(_) | Screen::TimeTrial(_)) => {
+                self.state = State::Racing { paused: None };
+                vec![SessionEvent::RaceStarted]
+            }
+            (State::Idle, _) => vec![],
+
+            (State::Racing { .. }, Screen::Pause(pause)) => {
+                self.state = State::Racing {
+                    paused: Some(pause.selected),
+                };
+                vec![]
+            }
+            // restarting goes straight back into the race, so it's the only
+            // time we see race frames after the race has ended. Without a
+            // transition in between, it's only a restart if the race is
+            // back at the start - otherwise Restart was hovered and the race
+            // carried on
+            (State::Restarting, Screen::Race(_) | Screen::TimeTrial(_))
+            | (
+                State::Racing {
+                    paused: Some(PauseOption::Restart),
+                },
+                Screen::Race(Race { starting: true, .. }),
+            ) => {
+                self.state = State::Racing { paused: None };
+                vec![
+                    SessionEvent::RaceEnded {
+                        outcome: RaceOutcome::Aborted,
+                    },
+                    SessionEvent::RaceStarted,
+                ]
+            }
+            (State::Restarting, _) => self.end(RaceOutcome::Aborted),
+            (State::Racing { .. }, Screen::Race(_) | Screen::TimeTrial(_)) => {
+                self.state = State::Racing { paused: None };
+                vec![]
+            }
+            (State::Racing { .. }, Screen::RaceResult(_) | Screen::TimeTrialResult(_)) => {
+                self.end(RaceOutcome::Finished)
+            }
+            (State::Racing { paused }, _) => {
+                let outcome = match paused {
+                    Some(PauseOption::Restart | PauseOption::Quit) => RaceOutcome::Aborted,
+                    _ => RaceOutcome::Incomplete,
+                };
+
+                self.end(outcome)
+            }
+        }
+    }
+
+    fn end(&mut self, outcome: RaceOutcome) -> Vec<SessionEvent> {
+        self.state = State::Idle;
+
+        vec![SessionEvent::RaceEnded { outcome }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RaceOutcome, Session, SessionEvent};
+    use crate::screens::highlights::{Highlights, HighlightsOption};
+    use crate::screens::loading::Loading;
+    use crate::screens::main_menu::MainMenu;
+    use crate::screens::online_lobby::OnlineLobby;
+    use crate::screens::pause::{Pause, PauseOption};
+    use crate::screens::race::Race;
+    use crate::screens::replay::Replay;
+    use crate::screens::time_trial::TimeTrial;
+    use crate::screens::time_trial_result::TimeTrialResult;
+    use crate::screens::Screen;
+    use crate::time::RaceTime;
+    use pretty_assertions::assert_eq;
+
+    fn race() -> Screen {
+        Screen::Race(Race {
+            players: vec![],
+            starting: false,
+            online: false,
+        })
+    }
+
+    fn pause(selected: PauseOption) -> Screen {
+        Screen::Pause(Pause { selected })
+    }
+
+    fn run(screens: &[Screen]) -> Vec<SessionEvent> {
+        let mut session = Session::new();
+
+        screens.iter().flat_map(|s| session.update(s)).collect()
+    }
+
+    #[test]
+    fn race_stopping_without_results_is_incomplete() {
+        let events = run(&[race(), race(), Screen::Unknown, Screen::Loading(Loading {})]);
+
+        assert_eq!(
+            events,
+            vec![
+                SessionEvent::RaceStarted,
+                SessionEvent::RaceEnded {
+                    outcome: RaceOutcome::Incomplete
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn quitting_from_pause_is_aborted() {
+        let events = run(&[
+            race(),
+            pause(PauseOption::Continue),
+            pause(PauseOption::Quit),
+            Screen::Unknown,
+            Screen::Loading(Loading {}),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![
+                SessionEvent::RaceStarted,
+                SessionEvent::RaceEnded {
+                    outcome: RaceOutcome::Aborted
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn continuing_from_pause_keeps_racing() {
+        let events = run(&[
+            race(),
+            pause(PauseOption::Quit),
+            pause(PauseOption::Continue),
+            race(),
+        ]);
+
+        assert_eq!(events, vec![SessionEvent::RaceStarted]);
+    }
+
+    #[test]
+    fn restarting_starts_a_new_race() {
+        let events = run(&[race(), pause(PauseOption::Restart), Screen::Unknown, race()]);
+
+        assert_eq!(
+            events,
+            vec![
+                SessionEvent::RaceStarted,
+                SessionEvent::RaceEnded {
+                    outcome: RaceOutcome::Aborted
+                },
+                SessionEvent::RaceStarted,
+            ]
+        );
+    }
+
+    #[test]
+    fn restarting_from_the_start_line_is_a_new_race() {
+        let starting = Screen::Race(Race {
+            players: vec![],
+            starting: true,
+            online: false,
+        });
+        let events = run(&[race(), pause(PauseOption::Restart), starting]);
+
+        assert_eq!(
+            events,
+            vec![
+                SessionEvent::RaceStarted,
+                SessionEvent::RaceEnded {
+                    outcome: RaceOutcome::Aborted
+                },
+                SessionEvent::RaceStarted,
+            ]
+        );
+    }
+
+    #[test]
+    fn hovering_restart_and_unpausing_keeps_racing() {
+        let events = run(&[race(), pause(PauseOption::Restart), race(), race()]);
+
+        assert_eq!(events, vec![SessionEvent::RaceStarted]);
+    }
+
+    #[test]
+    fn time_trials_finish_with_the_final_time() {
+        let clock = |millis| {
+            Screen::TimeTrial(TimeTrial {
+                time: RaceTime::from_millis(millis),
+                laps: vec![],
+            })
+        };
+        let events = run(&[
+            clock(1_000),
+            clock(95_000),
+            Screen::TimeTrialResult(TimeTrialResult {
+                time: RaceTime::from_millis(95_120),
+                laps: vec![],
+                new_record: false,
+            }),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![
+                SessionEvent::RaceStarted,
+                SessionEvent::PlayerFinished {
+                    player: 0,
+                    position: None,
+                    time: Some(RaceTime::from_millis(95_120)),
+                },
+                SessionEvent::RaceEnded {
+                    outcome: RaceOutcome::Finished
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&events[1]).unwrap(),
+            serde_json::json!({ "player": "player_one", "time": "1:35.120" })
+        );
+    }
+
+    #[test]
+    fn online_lasts_until_the_offline_menus() {
+        let mut session = Session::new();
+        let lobby = Screen::OnlineLobby(OnlineLobby { racers: vec![] });
+
+        let online: Vec<_> = [lobby, race(), Screen::MainMenu(MainMenu {}), race()]
+            .iter()
+            .map(|screen| {
+                session.update(screen);
+                session.is_online()
+            })
+            .collect();
+
+        assert_eq!(online, vec![true, true, false, false]);
+    }
+
+    #[test]
+    fn the_highlight_reel_is_not_a_race() {
+        let highlights = |selected| Screen::Highlights(Highlights { selected });
+        let events = run(&[
+            highlights(HighlightsOption::HighlightReel),
+            Screen::Unknown,
+            Screen::Replay(Replay {}),
+            Screen::Replay(Replay {}),
+            highlights(HighlightsOption::NextRace),
+            Screen::Loading(Loading {}),
+            race(),
+            Screen::Replay(Replay {}),
+            race(),
+        ]);
+
+        assert_eq!(events, vec![SessionEvent::RaceStarted]);
+    }
+}
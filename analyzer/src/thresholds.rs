@@ -0,0 +1,74 @@
+use crate::frame::Frame;
+use crate::screens::{intro, loading, main_menu, match_result, race, select_character};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// How far a hash can be from a reference and still count as a match, for each
+// reference. They're kept in a manifest alongside the references, so that
+// `kart-tune` can work them out from the labelled corpus and write them back.
+
+/// Where the manifest is, for the tools that rewrite it
+pub const MANIFEST_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/references/thresholds.json");
+
+lazy_static! {
+    static ref THRESHOLDS: BTreeMap<String, u32> = serde_json::from_str(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/references/thresholds.json"
+    )))
+    .expect("the thresholds manifest is invalid");
+}
+
+/// The largest distance from the reference `name` that's still a match
+pub fn threshold(name: &str) -> u32 {
+    *THRESHOLDS
+        .get(name)
+        .unwrap_or_else(|| panic!("no threshold for '{name}' in the manifest"))
+}
+
+/// Every threshold in the manifest, by reference
+pub fn thresholds() -> &'static BTreeMap<String, u32> {
+    &THRESHOLDS
+}
+
+/// How far part of a frame was from one of the references
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Sample {
+    pub reference: String,
+
+    /// The part of the (1280x720) frame that was hashed, `[x, y, width, height]`
+    pub region: [u32; 4],
+
+    /// Whose part of the screen it was, for references checked per player
+    pub player: Option<u8>,
+
+    /// What it would be read as, when the reference is one of a set
+    pub label: Option<String>,
+
+    pub distance: u32,
+}
+
+/// The distance from every reference with a threshold that's checked against
+/// a single frame, whether or not `analyze` would get as far as checking it
+pub fn samples(frame: &Frame) -> Vec<Sample> {
+    let mut samples = vec![
+        intro::sample(frame),
+        loading::sample(frame),
+        main_menu::sample(frame),
+        select_character::sample(frame),
+    ];
+    samples.extend(match_result::samples(frame));
+    samples.extend(race::samples(frame));
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::thresholds;
+
+    #[test]
+    fn manifest_loads() {
+        assert!(thresholds().contains_key("race/position"));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::color::max_color_diff;
+use crate::digits::read_cell;
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
+// a digit read with less confidence than this is more likely a blank or
+// smudged place than part of a time
+const MIN_CONFIDENCE: f32 = 0.5;
+
+/// A race or lap time, as it's displayed in game (`m:ss.mmm`)
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct RaceTime {
+    millis: u32,
+}
+
+impl RaceTime {
+    pub fn from_millis(millis: u32) -> Self {
+        RaceTime { millis }
+    }
+
+    pub fn as_millis(&self) -> u32 {
+        self.millis
+    }
+}
+
+impl Display for RaceTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let minutes = self.millis / 60_000;
+        let seconds = (self.millis / 1_000) % 60;
+        let millis = self.millis % 1_000;
+
+        write!(f, "{minutes}:{seconds:02}.{millis:03}")
+    }
+}
+
+impl Serialize for RaceTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Where the digits of a `m:ss.mmm` time sit, relative to the top left of the
+/// time. Timers are drawn in different sizes depending on the screen, so each
+/// screen describes its own.
+pub(crate) struct TimeLayout {
+    pub digit_width: u32,
+    pub digit_height: u32,
+    pub offsets: [u32; 6],
+}
+
+impl TimeLayout {
+    pub fn width(&self) -> u32 {
+        self.offsets[5] + self.digit_width
+    }
+}
+
+/// Reads a time drawn in white (like all the HUD timers) with its top left
+/// corner at `[x, y]`. Every digit has to be read clearly - a time with a
+/// place we're not sure of is no time at all.
+pub(crate) fn read_time(
+    frame: &image::DynamicImage,
+    [x, y]: [u32; 2],
+    layout: &TimeLayout,
+) -> Option<RaceTime> {
+    let mut section = frame.crop_imm(x, y, layout.width(), layout.digit_height);
+    max_color_diff(&mut section, 200);
+    // digits are read as dark on a light background
+    section.invert();
+
+    let digits = layout
+        .offsets
+        .iter()
+        .map(|offset| {
+            read_cell(&section, *offset, layout.digit_width, layout.digit_height)
+                .filter(|digit| digit.confidence >= MIN_CONFIDENCE)
+                .map(|digit| u32::from(digit.value))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let &[minutes, ten_seconds, seconds, ms_hundreds, ms_tens, ms_ones] = digits.as_slice() else {
+        return None;
+    };
+
+    if ten_seconds > 5 {
+        // not a real time - we've probably read something that isn't a timer
+        return None;
+    }
+
+    let millis = minutes * 60_000
+        + (ten_seconds * 10 + seconds) * 1_000
+        + ms_hundreds * 100
+        + ms_tens * 10
+        + ms_ones;
+
+    Some(RaceTime::from_millis(millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_time, RaceTime, TimeLayout};
+    use crate::color::max_color_diff;
+    use image::{DynamicImage, GenericImage, GenericImageView};
+
+    // scoreboard digits, laid out with gaps for the `:` and `.`
+    const LAYOUT: TimeLayout = TimeLayout {
+        digit_width: 19,
+        digit_height: 38,
+        offsets: [0, 30, 49, 79, 98, 117],
+    };
+
+    macro_rules! scoreboard_digit {
+        ($score:literal, $place:literal) => {
+            (
+                include_bytes!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/spec-data/digits/match_result_score_",
+                    $score,
+                    ".png"
+                ))
+                .as_slice(),
+                $place,
+            )
+        };
+    }
+
+    /// Lays digits cut off the match results out like a timer, in white on
+    /// black as the HUD draws them
+    fn timer(digits: [(&[u8], u32); 6]) -> DynamicImage {
+        let mut timer = DynamicImage::new_rgb8(LAYOUT.width(), LAYOUT.digit_height);
+
+        for ((data, place), offset) in digits.iter().zip(LAYOUT.offsets) {
+            let mut score = image::load_from_memory(data).expect("failed to open image");
+            // some rows are dark on their colour and some are faded, so pick
+            // the digits out whichever way round they are
+            max_color_diff(&mut score, 130);
+            if score.to_luma8().pixels().filter(|p| p.0[0] == 0).count() * 2
+                > (score.width() * score.height()) as usize
+            {
+                score.invert();
+            }
+            score.invert();
+
+            let digit = score.view(place * 23, 0, LAYOUT.digit_width, LAYOUT.digit_height);
+            timer.copy_from(&*digit, offset, 0).unwrap();
+        }
+
+        timer
+    }
+
+    #[test]
+    fn reads_each_place_of_a_time() {
+        let time = timer([
+            scoreboard_digit!("22", 0),
+            scoreboard_digit!("43", 1),
+            scoreboard_digit!("44", 0),
+            scoreboard_digit!("55", 0),
+            scoreboard_digit!("76", 1),
+            scoreboard_digit!("77", 0),
+        ]);
+
+        assert_eq!(
+            read_time(&time, [0, 0], &LAYOUT).map(|t| t.to_string()),
+            Some("2:34.567".to_string())
+        );
+    }
+
+    #[test]
+    fn unclear_places_are_not_read() {
+        let time = timer([
+            scoreboard_digit!("22", 0),
+            scoreboard_digit!("43", 1),
+            scoreboard_digit!("44", 0),
+            scoreboard_digit!("55", 0),
+            scoreboard_digit!("76", 1),
+            scoreboard_digit!("77", 0),
+        ]);
+        assert!(read_time(&time, [0, 0], &LAYOUT).is_some());
+
+        // only broken up bits of the last 7's downstroke are left, which
+        // looks like a 1 - but not a clear enough one to be read as it
+        let mut smudged = time.clone();
+        for y in 0..LAYOUT.digit_height {
+            for x in 0..LAYOUT.digit_width {
+                if y < 16 || y % 3 == 0 {
+                    smudged.put_pixel(LAYOUT.offsets[5] + x, y, image::Rgba([0, 0, 0, 255]));
+                }
+            }
+        }
+
+        assert_eq!(read_time(&smudged, [0, 0], &LAYOUT), None);
+    }
+
+    #[test]
+    fn formats_like_the_hud() {
+        assert_eq!(RaceTime::from_millis(83_456).to_string(), "1:23.456");
+        assert_eq!(RaceTime::from_millis(5_007).to_string(), "0:05.007");
+    }
+}
@@ -0,0 +1,230 @@
+use crate::frame::Frame;
+use crate::glyphs;
+use crate::screens::Screen;
+use crate::thresholds::{samples, thresholds, Sample};
+use crate::SCREENS;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// What `analyze` looked at to come to its answer, for working out why a frame
+// came out wrong. The hashed regions are all sampled, whichever screen was
+// picked, so it's easy to see how close the others came.
+
+const MATCHED: Rgb<u8> = Rgb([0, 255, 0]);
+const MISSED: Rgb<u8> = Rgb([255, 48, 48]);
+const TEXT: Rgb<u8> = Rgb([255, 255, 255]);
+const BACKGROUND: Rgb<u8> = Rgb([0, 0, 0]);
+// the font is tiny, so it's drawn this much bigger
+const TEXT_SCALE: u32 = 2;
+
+/// One of the screens `analyze` checked, in the order they were checked
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Check {
+    pub screen: &'static str,
+    pub matched: bool,
+    /// The parts of the frame the screen reads, as `[x, y, width, height]`
+    pub regions: Vec<[u32; 4]>,
+}
+
+/// How close a region was to one of the references, and how close it had to
+/// be to match
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Decision {
+    #[serde(flatten)]
+    pub sample: Sample,
+    pub threshold: Option<u32>,
+}
+
+impl Decision {
+    pub fn matched(&self) -> bool {
+        self.threshold
+            .is_some_and(|threshold| self.sample.distance <= threshold)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct Trace {
+    pub checks: Vec<Check>,
+    pub decisions: Vec<Decision>,
+}
+
+/// The same as `analyze`, along with everything it checked on the way
+pub fn analyze_with_trace(frame: &DynamicImage) -> (Option<Screen>, Trace) {
+    let frame = Frame::new(frame);
+
+    let mut checks = vec![];
+    let screen = crate::detect(&frame, |screen, matched| {
+        let regions = SCREENS
+            .iter()
+            .find(|(name, ..)| *name == screen)
+            .map_or(vec![], |(.., regions)| regions());
+
+        checks.push(Check {
+            screen,
+            matched,
+            regions,
+        })
+    });
+
+    let decisions = samples(&frame)
+        .into_iter()
+        .map(|sample| Decision {
+            threshold: thresholds().get(&sample.reference).copied(),
+            sample,
+        })
+        .collect();
+
+    (screen, Trace { checks, decisions })
+}
+
+impl Trace {
+    /// The decisions for each region, closest first
+    pub fn regions(&self) -> BTreeMap<[u32; 4], Vec<&Decision>> {
+        let mut regions: BTreeMap<[u32; 4], Vec<&Decision>> = BTreeMap::new();
+        for decision in &self.decisions {
+            regions
+                .entry(decision.sample.region)
+                .or_default()
+                .push(decision);
+        }
+
+        for decisions in regions.values_mut() {
+            decisions.sort_by_key(|d| d.sample.distance);
+        }
+
+        regions
+    }
+
+    /// Draws the trace over the frame: each region boxed in green if it
+    /// matched a reference and red if it didn't, labelled with the closest
+    /// reference, the rest of the regions the matching screen reads boxed in
+    /// white, and the screens that were checked in the top left
+    pub fn render(&self, frame: &DynamicImage) -> RgbImage {
+        let mut image = Frame::new(frame).to_rgb8();
+        let decided = self.regions();
+
+        let matched = self.checks.iter().filter(|check| check.matched);
+        for region in matched.flat_map(|check| &check.regions) {
+            if !decided.contains_key(region) {
+                draw_box(&mut image, *region, TEXT);
+            }
+        }
+
+        for (region, decisions) in decided {
+            let matched = decisions.iter().any(|d| d.matched());
+            let colour = if matched { MATCHED } else { MISSED };
+            draw_box(&mut image, region, colour);
+
+            let closest = decisions[0];
+            let name = closest
+                .sample
+                .label
+                .clone()
+                .unwrap_or_else(|| closest.sample.reference.clone());
+            let threshold = closest.threshold.map_or("-".into(), |t| t.to_string());
+            let [x, y, _, height] = region;
+            draw_text(
+                &mut image,
+                &format!("{name} {}/{threshold}", closest.sample.distance),
+                [x, y + height + 2],
+                colour,
+            );
+        }
+
+        let line_height = (7 + 2) * TEXT_SCALE;
+        for (i, check) in self.checks.iter().enumerate() {
+            let text = format!(
+                "{} {}",
+                check.screen,
+                if check.matched { "yes" } else { "no" }
+            );
+            let colour = if check.matched { MATCHED } else { TEXT };
+            draw_text(&mut image, &text, [4, 4 + i as u32 * line_height], colour);
+        }
+
+        image
+    }
+}
+
+fn draw_box(image: &mut RgbImage, [x, y, width, height]: [u32; 4], colour: Rgb<u8>) {
+    // nothing of a box that starts off the image can be drawn
+    if x >= image.width() || y >= image.height() {
+        return;
+    }
+
+    let right = (x + width).min(image.width() - 1);
+    let bottom = (y + height).min(image.height() - 1);
+
+    for px in x..=right {
+        image.put_pixel(px, y, colour);
+        image.put_pixel(px, bottom, colour);
+    }
+    for py in y..=bottom {
+        image.put_pixel(x, py, colour);
+        image.put_pixel(right, py, colour);
+    }
+}
+
+/// Draws `text` on a black background, moved back onto the image if it would
+/// run off the edge
+fn draw_text(image: &mut RgbImage, text: &str, [x, y]: [u32; 2], colour: Rgb<u8>) {
+    // the font has no underscores
+    let rendered = glyphs::render(&text.replace('_', " "));
+
+    let width = (rendered.width() + 2) * TEXT_SCALE;
+    let height = (rendered.height() + 2) * TEXT_SCALE;
+    let x = x.min(image.width().saturating_sub(width));
+    let y = y.min(image.height().saturating_sub(height));
+    for dy in 0..height {
+        for dx in 0..width {
+            let (px, py) = (x + dx, y + dy);
+            if px >= image.width() || py >= image.height() {
+                continue;
+            }
+
+            let (gx, gy) = (dx / TEXT_SCALE, dy / TEXT_SCALE);
+            let ink = gx >= 1
+                && gy >= 1
+                && rendered
+                    .get_pixel_checked(gx - 1, gy - 1)
+                    .is_some_and(|p| p.0[0] > 0);
+
+            image.put_pixel(px, py, if ink { colour } else { BACKGROUND });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_with_trace, draw_box};
+    use crate::analyze;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn trace_comes_to_the_same_answer() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs/test_1.jpg");
+        let frame = image::open(path).unwrap();
+
+        let (screen, trace) = analyze_with_trace(&frame);
+
+        assert_eq!(screen, analyze(&frame));
+        assert_eq!(trace.checks.iter().filter(|c| c.matched).count(), 1);
+        assert!(trace.decisions.iter().all(|d| d.threshold.is_some()));
+        assert!(trace.checks.iter().all(|c| !c.regions.is_empty()));
+    }
+
+    #[test]
+    fn boxes_are_cut_off_at_the_edge() {
+        let mut image = RgbImage::new(10, 10);
+        let colour = Rgb([255, 0, 0]);
+
+        draw_box(&mut image, [5, 5, 20, 20], colour);
+        draw_box(&mut image, [12, 3, 4, 4], colour);
+        draw_box(&mut image, [3, 12, 4, 4], colour);
+
+        assert_eq!(image[(5, 5)], colour);
+        assert_eq!(image[(9, 9)], colour);
+        assert_eq!(image.pixels().filter(|p| **p == colour).count(), 16);
+    }
+}
@@ -1,17 +1,18 @@
 use crate::color::{get_overall_average, lightness};
+use crate::frame::Frame;
 
-pub fn is_splitscreen(frame: &image::DynamicImage) -> bool {
+/// Use `Frame::is_splitscreen`, which only works this out once
+pub(crate) fn is_splitscreen(frame: &Frame) -> bool {
     let width = frame.width();
     let height = frame.height();
 
-    let top = get_overall_average(&frame.crop_imm((width / 2) - 1, 2, 2, 4));
-    let left = get_overall_average(&frame.crop_imm(2, (height / 2) - 1, 4, 2));
-    let right = get_overall_average(&frame.crop_imm(width - 4, (height / 2) - 1, 4, 2));
-    let bottom = get_overall_average(&frame.crop_imm((width / 2) - 1, height - 4, 2, 4));
+    let top = get_overall_average(&*frame.view((width / 2) - 1, 2, 2, 4));
+    let left = get_overall_average(&*frame.view(2, (height / 2) - 1, 4, 2));
+    let right = get_overall_average(&*frame.view(width - 4, (height / 2) - 1, 4, 2));
+    let bottom = get_overall_average(&*frame.view((width / 2) - 1, height - 4, 2, 4));
 
-    let rgb = frame.to_rgb16();
-    let top_left = lightness(rgb.get_pixel(0, 0));
-    let bottom_left = lightness(rgb.get_pixel(0, height - 1));
+    let top_left = lightness(&frame.rgb16_pixel(0, 0));
+    let bottom_left = lightness(&frame.rgb16_pixel(0, height - 1));
 
     return top <= 5000
         && bottom <= 5000
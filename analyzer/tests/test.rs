@@ -1,6 +1,4 @@
-use std::io::Write;
-
-use analyzer::analyze;
+use analyzer::{analyze, analyze_frame, Classifier, Frame};
 use image;
 use pretty_assertions;
 use rayon::prelude::*;
@@ -13,8 +11,11 @@ struct Event<'a, T: Serialize> {
     body: T,
 }
 
+/// Run with `UPDATE_EXPECT=1` to write the current output over any
+/// expectations that don't match it. `kart-review` shows what would change.
 #[test]
 fn inputs_match_outputs() {
+    let update = std::env::var("UPDATE_EXPECT").is_ok_and(|v| v == "1");
     let input_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs");
     let paths = std::fs::read_dir(input_path).expect("Couldn't open inputs/ dir");
     let paths: Vec<_> = paths.filter_map(|p| p.ok()).map(|p| p.path()).collect();
@@ -55,10 +56,16 @@ fn inputs_match_outputs() {
                 .replace("/inputs/", "/outputs/")
                 .replace(".jpg", ".json");
 
-            // NOTE: uncomment the 3 lines below to update the expectations
-            // use std::io::Write;
-            // let mut expectation_file = std::fs::File::create(&expectation_path).expect("couldn't open expectation file");
-            // write!(expectation_file, "{actual}").unwrap();
+            if update {
+                let expectation = std::fs::read_to_string(&expectation_path).ok();
+                if expectation.as_ref() != Some(&actual) {
+                    std::fs::write(&expectation_path, &actual)
+                        .expect("couldn't write expectation file");
+                    eprintln!("updated {expectation_path}");
+                }
+
+                return true;
+            }
 
             let expectation =
                 std::fs::read_to_string(&expectation_path).expect("no expectation found");
@@ -78,37 +85,26 @@ fn inputs_match_outputs() {
 
     // we filter out all invalid results so if these don't match, we've failed
     assert_eq!(paths.len(), results.len());
-
-    // NOTE: uncomment the below line to rebuild the review page
-    // create_review_file();
 }
 
-// we only use this sometimes
-#[allow(dead_code)]
-fn create_review_file() {
+/// The classifier skips what it can, but it shouldn't come to a different
+/// answer. Each frame goes through twice, the second time as an unchanged one.
+#[test]
+fn classifier_agrees_with_analyze() {
     let input_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/inputs");
-    let template_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/template.html");
-    let review_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/review.html");
-    let paths = std::fs::read_dir(input_path).expect("Couldn't open inputs/ dir");
-    let paths: Vec<_> = paths.filter_map(|p| p.ok()).map(|p| p.path()).collect();
-
-    let rows = paths.par_iter().map(|path| {
-        let path_str = path.to_str().expect("not a valid path name");
-        let expectation_path = path_str.to_string().replace("/inputs/", "/outputs/").replace(".jpg", ".json");
-        let expectation = std::fs::read_to_string(expectation_path).expect("no expectation found");
-
-        format!("<tr><td colspan=\"3\"><b>{path_str}</b></td></tr><tr><td><img src=\"{path_str}\" /></td><td><pre>{expectation}</pre></td><td><input type=\"checkbox\" /></td></tr>")
-    }).collect::<Vec<_>>();
-
-    let contents = rows.join("\n");
-
-    let template = std::fs::read_to_string(template_path).expect("couldn't find template");
+    let mut paths: Vec<_> = std::fs::read_dir(input_path)
+        .expect("Couldn't open inputs/ dir")
+        .filter_map(|p| p.ok())
+        .map(|p| p.path())
+        .collect();
+    paths.sort();
 
-    let review_page = template.replace("{/*content*/}", &contents);
+    let mut classifier = Classifier::new();
+    for path in paths {
+        let frame = Frame::new(&image::open(&path).expect("couldn't open input_image"));
+        let expected = analyze_frame(&frame);
 
-    write!(
-        std::fs::File::create(review_path).expect("couldn't open review file for writing"),
-        "{review_page}"
-    )
-    .unwrap();
+        assert_eq!(classifier.analyze(&frame), expected, "{path:?}");
+        assert_eq!(classifier.analyze(&frame), expected, "{path:?} again");
+    }
 }
@@ -64,6 +64,17 @@ where
             return;
         }
 
+        self.update_inner(data.clone());
+        self.send(event_type, data);
+    }
+
+    /// Sends `data` straight away, even if it's the same as the last thing
+    /// sent. This doesn't count towards skipping repeats of anything else.
+    pub fn emit_now(&self, event_type: &str, data: &T) {
+        self.send(event_type, data);
+    }
+
+    fn send(&self, event_type: &str, data: &T) {
         let now = Utc::now();
 
         let event = Event {
@@ -76,8 +87,6 @@ where
             events: vec![event],
         };
 
-        self.update_inner(data.clone());
-
         match &self.emitter {
             Some(emitter) => {
                 emitter.queue(emittable.clone());
@@ -25,9 +25,7 @@ fn main() {
 
 fn process(path: &PathBuf, emitter: &emitter::Emit<analyzer::Screen>) {
     println!("Processing {:?}", path);
-    let image = image::open(&path)
-        .expect("failed to open static image")
-        .resize(1280, 720, image::imageops::Nearest);
+    let image = image::open(&path).expect("failed to open static image");
 
     let result = analyzer::analyze(&image);
 
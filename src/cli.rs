@@ -1,4 +1,5 @@
 use clap::{Parser, ValueEnum};
+use stream::pipeline::DropPolicy;
 
 #[derive(ValueEnum, Clone, Debug)]
 pub enum LogLevel {
@@ -23,4 +24,23 @@ pub struct Cli {
 
     #[arg(long)]
     pub store_logs: bool,
+
+    /// Skip frames that haven't changed since the last one, and check the
+    /// screen each frame looks most like first. For slower capture boxes
+    #[arg(long)]
+    pub classify: bool,
+
+    /// Reuse what was read from the race HUD while it hasn't changed
+    #[arg(long)]
+    pub cache_regions: bool,
+
+    /// How many frames can wait between each stage, from capturing them to
+    /// sending what they were
+    #[arg(long, default_value_t = 4)]
+    pub queue_size: usize,
+
+    /// What to drop when a stage can't keep up: `oldest`, `newest`, or
+    /// `every:<n>` to only take every nth frame from the camera
+    #[arg(long, default_value_t = DropPolicy::Oldest)]
+    pub drop: DropPolicy,
 }
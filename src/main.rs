@@ -1,4 +1,4 @@
-use analyzer::{analyze, Screen};
+use analyzer::{analyze_frame, Classifier, Frame, RegionCache, Screen, Session, SessionEvent};
 use clap::Parser;
 use stream;
 
@@ -6,35 +6,128 @@ use log::{error, info};
 use log_err::LogErrResult;
 use simplelog;
 use std::fs::File;
+use std::sync::{mpsc, Arc};
+use stream::pipeline::{Counters, Pipeline};
 
 mod cli;
 
+/// What the analyze stage worked out from a frame, for the emit stage to send
+struct Analyzed {
+    screen: Option<Screen>,
+    /// Whether to send the screen itself. Unknown screens aren't
+    emit: bool,
+    events: Vec<SessionEvent>,
+    #[cfg(feature = "minimap")]
+    standings: Vec<analyzer::minimap::Standing>,
+    fps: u128,
+}
+
 fn main() {
     let args = cli::Cli::parse();
     init_logger(&args);
 
     let frame_saver = FrameSaver::new(args.store_frames);
 
-    let emitter = emitter::Emit::new(emitter::Mode::Real);
+    let pipeline = Pipeline::new(args.queue_size, args.drop);
+    let counters = pipeline.counters.clone();
+
+    let mut session = Session::new();
+    let mut classifier = args.classify.then(Classifier::new);
+    let regions = args.cache_regions.then(|| Arc::new(RegionCache::new()));
+    #[cfg(feature = "minimap")]
+    let mut progress = analyzer::minimap::Progress::new();
 
-    stream::device::from_device(move |frame, count| {
-        frame_saver.save(frame, count);
+    // sending waits for room, so if emitting falls behind, frames are
+    // dropped before they're analyzed rather than after
+    let (to_emit, analyzed) = mpsc::sync_channel::<Analyzed>(args.queue_size);
+    std::thread::spawn(move || send_analyzed(analyzed, &counters));
+
+    stream::device::from_device(pipeline, move |frame, count| {
+        frame_saver.save(&frame, count);
         let start = std::time::Instant::now();
 
-        let res = analyze(frame);
+        let frame = match &regions {
+            Some(regions) => Frame::from(frame).with_cache(regions),
+            None => Frame::from(frame),
+        };
+        let mut res = match &mut classifier {
+            Some(classifier) => classifier.analyze(&frame),
+            None => analyze_frame(&frame),
+        };
+
+        let mut emit = false;
+        let mut events = vec![];
+        #[cfg(feature = "minimap")]
+        let mut standings = vec![];
+
+        if let Some(res) = &mut res {
+            events = session.update(res);
+
+            // nothing on a race frame says whether it's online, so that
+            // comes from how we got here
+            if let Screen::Race(race) = res {
+                race.online = session.is_online();
+            }
 
-        if let Some(res) = &res {
             // we want to not emit "unknown screen" events
-            if res != &Screen::Unknown {
-                emitter.emit(res.event_type(), res);
+            emit = res != &Screen::Unknown;
+
+            #[cfg(feature = "minimap")]
+            if events.contains(&SessionEvent::RaceStarted) {
+                progress = analyzer::minimap::Progress::new();
+            }
+
+            #[cfg(feature = "minimap")]
+            if let Screen::Race(_) = res {
+                standings = progress.update(&analyzer::minimap::read(&frame));
             }
         }
 
         let end = std::time::Instant::now();
         let delta = end - start;
-        let fps = std::time::Duration::from_secs(1).as_micros() / delta.as_micros();
+        let fps = std::time::Duration::from_secs(1).as_micros() / delta.as_micros().max(1);
+
+        to_emit
+            .send(Analyzed {
+                screen: res,
+                emit,
+                events,
+                #[cfg(feature = "minimap")]
+                standings,
+                fps,
+            })
+            .log_expect("the emit stage has stopped");
+    });
+}
+
+/// The last stage of the pipeline, sending what each frame was analyzed as
+fn send_analyzed(analyzed: mpsc::Receiver<Analyzed>, counters: &Counters) {
+    let emitter = emitter::Emit::new(emitter::Mode::Real);
+    let session_emitter = emitter::Emit::new(emitter::Mode::Real);
+    #[cfg(feature = "minimap")]
+    let progress_emitter = emitter::Emit::new(emitter::Mode::Real);
+
+    for analyzed in analyzed {
+        if let (true, Some(screen)) = (analyzed.emit, &analyzed.screen) {
+            emitter.emit(screen.event_type(), screen);
+        }
+
+        for event in analyzed.events {
+            // alerts and incidents are only useful if they get there straight
+            // away
+            if let SessionEvent::Alert(_) | SessionEvent::Incident(_) = event {
+                session_emitter.emit_now(event.event_type(), &event);
+            } else {
+                session_emitter.emit(event.event_type(), &event);
+            }
+        }
+
+        #[cfg(feature = "minimap")]
+        if !analyzed.standings.is_empty() {
+            progress_emitter.emit("progress", &analyzed.standings);
+        }
 
-        let output = match &res {
+        let output = match &analyzed.screen {
             None => "Unknown".into(),
             Some(screen) => {
                 let json = serde_json::to_string(&screen);
@@ -42,8 +135,13 @@ fn main() {
             }
         };
 
-        info!("{output} ({fps} fps)");
-    });
+        info!(
+            "{output} ({} fps, {} of {} frames dropped)",
+            analyzed.fps,
+            counters.dropped(),
+            counters.captured()
+        );
+    }
 }
 
 struct FrameSaver {
@@ -66,7 +164,9 @@ impl FrameSaver {
     }
 
     fn save(&self, frame: &image::DynamicImage, count: usize) {
-        let Some(name) = &self.name else { return; };
+        let Some(name) = &self.name else {
+            return;
+        };
 
         let output_path = format!("frames/{}/frame_{:?}.jpg", name, count);
         if let Err(e) = frame.save(&output_path) {
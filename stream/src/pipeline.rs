@@ -0,0 +1,245 @@
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Frames go from the camera, to being decoded, to whoever's analyzing them,
+// each on its own thread with a short queue in between. When a stage falls
+// behind, its queue fills up and frames are dropped there, instead of piling
+// up inside the camera or holding it up.
+
+/// What to do with frames when the next stage can't keep up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Make room for the new frame by dropping the oldest one waiting. This
+    /// keeps the latest frame closest to what's on screen now
+    Oldest,
+    /// Drop the new frame, and keep the ones already waiting
+    Newest,
+    /// Only take every nth frame from the camera, dropping the newest if the
+    /// queue fills up anyway
+    EveryNth(usize),
+}
+
+impl DropPolicy {
+    /// What to do when a queue is full, once frames have been skipped
+    fn when_full(self) -> DropPolicy {
+        match self {
+            DropPolicy::EveryNth(_) => DropPolicy::Newest,
+            policy => policy,
+        }
+    }
+}
+
+impl FromStr for DropPolicy {
+    type Err = String;
+
+    /// `oldest`, `newest`, or `every:<n>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "oldest" => Ok(DropPolicy::Oldest),
+            "newest" => Ok(DropPolicy::Newest),
+            _ => match s.strip_prefix("every:").map(str::parse) {
+                Some(Ok(n)) if n > 0 => Ok(DropPolicy::EveryNth(n)),
+                _ => Err(format!(
+                    "expected `oldest`, `newest` or `every:<n>` (with n > 0), not `{s}`"
+                )),
+            },
+        }
+    }
+}
+
+impl Display for DropPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DropPolicy::Oldest => write!(f, "oldest"),
+            DropPolicy::Newest => write!(f, "newest"),
+            DropPolicy::EveryNth(n) => write!(f, "every:{n}"),
+        }
+    }
+}
+
+/// How many frames have come from the camera, and how many were dropped on
+/// the way through
+#[derive(Debug, Default)]
+pub struct Counters {
+    captured: AtomicUsize,
+    undecoded: AtomicUsize,
+    unanalyzed: AtomicUsize,
+}
+
+impl Counters {
+    pub fn captured(&self) -> usize {
+        self.captured.load(Ordering::Relaxed)
+    }
+
+    /// Frames dropped before they were decoded
+    pub fn undecoded(&self) -> usize {
+        self.undecoded.load(Ordering::Relaxed)
+    }
+
+    /// Frames that were decoded, then dropped before they were analyzed
+    pub fn unanalyzed(&self) -> usize {
+        self.unanalyzed.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.undecoded() + self.unanalyzed()
+    }
+}
+
+/// How frames get from the camera to `on_frame`
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    /// How many frames can wait between each stage
+    pub capacity: usize,
+    pub policy: DropPolicy,
+    pub counters: Arc<Counters>,
+}
+
+impl Pipeline {
+    pub fn new(capacity: usize, policy: DropPolicy) -> Self {
+        Pipeline {
+            capacity: capacity.max(1),
+            policy,
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    pub(crate) fn captured(&self) {
+        self.counters.captured.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The queue between the camera and decoding
+    pub(crate) fn decode_queue<T>(&self) -> (Input<T>, Receiver<T>) {
+        queue(self.capacity, self.policy, self.counters.clone(), |c| {
+            &c.undecoded
+        })
+    }
+
+    /// The queue between decoding and `on_frame`
+    pub(crate) fn analyze_queue<T>(&self) -> (Input<T>, Receiver<T>) {
+        queue(
+            self.capacity,
+            self.policy.when_full(),
+            self.counters.clone(),
+            |c| &c.unanalyzed,
+        )
+    }
+}
+
+/// The sending end of a queue between two stages, which drops frames rather
+/// than waiting for room
+pub(crate) struct Input<T> {
+    sender: Sender<T>,
+    // kept to drop the oldest frame waiting when there's no room
+    receiver: Receiver<T>,
+    policy: DropPolicy,
+    pushed: usize,
+    counters: Arc<Counters>,
+    dropped: fn(&Counters) -> &AtomicUsize,
+}
+
+fn queue<T>(
+    capacity: usize,
+    policy: DropPolicy,
+    counters: Arc<Counters>,
+    dropped: fn(&Counters) -> &AtomicUsize,
+) -> (Input<T>, Receiver<T>) {
+    let (sender, receiver) = crossbeam_channel::bounded(capacity);
+
+    let input = Input {
+        sender,
+        receiver: receiver.clone(),
+        policy,
+        pushed: 0,
+        counters,
+        dropped,
+    };
+
+    (input, receiver)
+}
+
+impl<T> Input<T> {
+    /// Passes `item` on to the next stage, unless it's dropped
+    pub(crate) fn push(&mut self, item: T) {
+        self.pushed += 1;
+
+        if let DropPolicy::EveryNth(n) = self.policy {
+            if (self.pushed - 1) % n != 0 {
+                self.drop_one();
+                return;
+            }
+        }
+
+        let mut item = item;
+        loop {
+            match self.sender.try_send(item) {
+                Ok(()) => return,
+                // the other end holds on to a receiver for as long as this
+                // does, so this can't happen
+                Err(TrySendError::Disconnected(_)) => return,
+                Err(TrySendError::Full(rejected)) => match self.policy {
+                    DropPolicy::Oldest => {
+                        if self.receiver.try_recv().is_ok() {
+                            self.drop_one();
+                        }
+                        item = rejected;
+                    }
+                    DropPolicy::Newest | DropPolicy::EveryNth(_) => {
+                        self.drop_one();
+                        return;
+                    }
+                },
+            }
+        }
+    }
+
+    fn drop_one(&self) {
+        (self.dropped)(&self.counters).fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DropPolicy, Pipeline};
+
+    fn run(policy: DropPolicy) -> (Vec<usize>, usize) {
+        let pipeline = Pipeline::new(2, policy);
+        let (mut input, output) = pipeline.decode_queue();
+
+        for frame in 0..6 {
+            input.push(frame);
+        }
+
+        (output.try_iter().collect(), pipeline.counters.undecoded())
+    }
+
+    #[test]
+    fn drops_the_oldest_frames() {
+        assert_eq!(run(DropPolicy::Oldest), (vec![4, 5], 4));
+    }
+
+    #[test]
+    fn drops_the_newest_frames() {
+        assert_eq!(run(DropPolicy::Newest), (vec![0, 1], 4));
+    }
+
+    #[test]
+    fn takes_every_nth_frame() {
+        assert_eq!(run(DropPolicy::EveryNth(3)), (vec![0, 3], 4));
+    }
+
+    #[test]
+    fn parses_policies() {
+        for policy in [
+            DropPolicy::Oldest,
+            DropPolicy::Newest,
+            DropPolicy::EveryNth(3),
+        ] {
+            assert_eq!(policy.to_string().parse(), Ok(policy));
+        }
+        assert!("every:0".parse::<DropPolicy>().is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use analyzer::analyze_with_trace;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+use util::{preview, terminal, timed_frame, Protocol};
+
+/// Look at frames the way the analyzer does, with the images shown inline in
+/// the terminal
+#[derive(Parser, Debug)]
+struct Args {
+    /// How to show images. Worked out from the terminal if it's not given
+    #[arg(long, value_enum)]
+    protocol: Option<Protocol>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Analyze frames, timing each of them
+    Analyze {
+        /// Show each frame, with the regions it was read from outlined
+        #[arg(long)]
+        show: bool,
+
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Show part of a frame, as `analyze` sees it
+    Crop {
+        file: PathBuf,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+
+        /// How many times bigger to show it
+        #[arg(long, default_value_t = 4)]
+        scale: u32,
+
+        /// Save the crop (unscaled) here as well
+        #[arg(long)]
+        save: Option<PathBuf>,
+    },
+    /// Show every region that was hashed and how close it came to matching
+    Trace {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Just show frames
+    Show {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+    let protocol = args.protocol.unwrap_or_else(Protocol::detect);
+
+    match args.command {
+        Command::Analyze { show, files } => {
+            let mut total = Duration::ZERO;
+            for path in &files {
+                let Some(image) = open(path) else {
+                    continue;
+                };
+
+                println!("{path:?}");
+                let (screen, delta) = timed_frame(&image);
+                total += delta;
+
+                if let (true, Some(screen)) = (show, &screen) {
+                    let outlined = preview::screen_regions(&image, screen);
+                    terminal::print_dynamic_image(protocol, &path.to_string_lossy(), &outlined);
+                }
+            }
+
+            if files.len() > 1 {
+                println!(
+                    "{} frames, {:?} on average",
+                    files.len(),
+                    total / files.len() as u32
+                );
+            }
+        }
+        Command::Crop {
+            file,
+            x,
+            y,
+            width,
+            height,
+            scale,
+            save,
+        } => {
+            let Some(image) = open(&file) else {
+                return;
+            };
+
+            let region = [x, y, width, height];
+            terminal::print_dynamic_image(
+                protocol,
+                &file.to_string_lossy(),
+                &preview::crop(&image, region, scale),
+            );
+
+            if let Some(save) = save {
+                if let Err(e) = preview::crop(&image, region, 1).save(&save) {
+                    eprintln!("Failed to save the crop to {save:?} - {e}");
+                }
+            }
+        }
+        Command::Trace { files } => {
+            for path in &files {
+                let Some(image) = open(path) else {
+                    continue;
+                };
+
+                let (screen, trace) = analyze_with_trace(&image);
+                println!("{path:?}: {:?}", screen.as_ref().map(|s| s.event_type()));
+                let traced = image::DynamicImage::ImageRgb8(trace.render(&image));
+                terminal::print_dynamic_image(protocol, &path.to_string_lossy(), &traced);
+            }
+        }
+        Command::Show { files } => {
+            for path in &files {
+                terminal::print_image_from_path(protocol, path);
+            }
+        }
+    }
+}
+
+fn open(path: &PathBuf) -> Option<image::DynamicImage> {
+    match image::open(path) {
+        Ok(i) => Some(i),
+        Err(e) => {
+            eprintln!("ERROR: Couldn't open image at {path:?} - {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use analyzer::{Frame, Screen};
+use image::{DynamicImage, Rgba};
+
+// Looking at the parts of a frame the analyzer reads. Regions are all given
+// against the frame as `analyze` sees it, resized to 1280x720.
+
+const OUTLINE: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Cuts `[x, y, width, height]` out of the frame, scaled up `scale` times so
+/// the small ones can be made out
+pub fn crop(frame: &DynamicImage, [x, y, width, height]: [u32; 4], scale: u32) -> DynamicImage {
+    let crop = Frame::new(frame).crop_imm(x, y, width, height);
+    let scale = scale.max(1);
+
+    crop.resize(
+        crop.width() * scale,
+        crop.height() * scale,
+        image::imageops::Nearest,
+    )
+}
+
+/// The frame with each of `regions` outlined
+pub fn outline(frame: &DynamicImage, regions: &[[u32; 4]]) -> DynamicImage {
+    let mut image = Frame::new(frame).to_rgba8();
+    let (max_x, max_y) = (image.width() - 1, image.height() - 1);
+
+    for [x, y, width, height] in regions {
+        // nothing of a region that starts off the frame can be outlined
+        if *x > max_x || *y > max_y {
+            continue;
+        }
+
+        let right = (x + width).min(max_x);
+        let bottom = (y + height).min(max_y);
+
+        for px in *x..=right {
+            image.put_pixel(px, *y, OUTLINE);
+            image.put_pixel(px, bottom, OUTLINE);
+        }
+        for py in *y..=bottom {
+            image.put_pixel(*x, py, OUTLINE);
+            image.put_pixel(right, py, OUTLINE);
+        }
+    }
+
+    DynamicImage::ImageRgba8(image)
+}
+
+/// The frame with the regions `screen` was read from outlined
+pub fn screen_regions(frame: &DynamicImage, screen: &Screen) -> DynamicImage {
+    outline(frame, &screen.regions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{outline, OUTLINE};
+    use image::{DynamicImage, GenericImageView};
+
+    #[test]
+    fn regions_are_cut_off_at_the_edge() {
+        let frame = DynamicImage::new_rgb8(1280, 720);
+
+        let image = outline(
+            &frame,
+            &[[1270, 710, 40, 40], [1300, 10, 5, 5], [10, 800, 5, 5]],
+        );
+
+        assert_eq!(image.get_pixel(1270, 710), OUTLINE);
+        assert_eq!(image.get_pixel(1279, 719), OUTLINE);
+    }
+}
@@ -0,0 +1,205 @@
+use base64::prelude::*;
+use image::{DynamicImage, Rgb};
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::Path;
+
+// Showing images inline in the terminal. Terminals don't agree on how, so
+// there's a choice of the protocols most of them understand between them.
+
+// anything bigger than this is shrunk to fit, so a whole frame doesn't take up
+// the whole terminal
+const MAX_SIZE: [u32; 2] = [640, 360];
+// kitty wants the image sent in pieces no bigger than this
+const KITTY_CHUNK: usize = 4096;
+// sixels are drawn from a palette. six levels of each channel is plenty to see
+// what's going on
+const SIXEL_LEVELS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Protocol {
+    /// iTerm2's inline images, which WezTerm also understands
+    Iterm,
+    /// kitty's graphics protocol, which Ghostty also understands
+    Kitty,
+    /// DEC sixels, for most everything else
+    Sixel,
+}
+
+impl Protocol {
+    /// Works out which protocol the terminal speaks from its environment.
+    /// Terminals that don't say are assumed to understand sixels.
+    pub fn detect() -> Protocol {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+
+        match var("TERM_PROGRAM").as_str() {
+            "iTerm.app" | "WezTerm" => return Protocol::Iterm,
+            "ghostty" => return Protocol::Kitty,
+            _ => {}
+        }
+
+        if var("TERM").contains("kitty") || std::env::var("KITTY_WINDOW_ID").is_ok() {
+            Protocol::Kitty
+        } else {
+            Protocol::Sixel
+        }
+    }
+
+    /// The escape sequence that draws `image` inline
+    pub fn encode(&self, name: &str, image: &DynamicImage) -> String {
+        let [max_width, max_height] = MAX_SIZE;
+        let image = if image.width() > max_width || image.height() > max_height {
+            image.resize(max_width, max_height, image::imageops::Triangle)
+        } else {
+            image.clone()
+        };
+
+        match self {
+            Protocol::Iterm => iterm(name, &image),
+            Protocol::Kitty => kitty(&image),
+            Protocol::Sixel => sixel(&image),
+        }
+    }
+}
+
+pub fn print_image_from_path(protocol: Protocol, path: &Path) {
+    println!("image path: {:?}", &path);
+    match image::open(path) {
+        Ok(image) => print_dynamic_image(protocol, &path.to_string_lossy(), &image),
+        Err(e) => eprintln!("ERROR: Couldn't open image at {path:?} - {e}"),
+    }
+}
+
+pub fn print_dynamic_image(protocol: Protocol, name: &str, image: &DynamicImage) {
+    println!("{}", protocol.encode(name, image));
+}
+
+fn png(image: &DynamicImage) -> Vec<u8> {
+    let mut c = Cursor::new(Vec::new());
+    image
+        .write_to(&mut c, image::ImageOutputFormat::Png)
+        .expect("Couldn't get the image to print");
+
+    c.into_inner()
+}
+
+fn iterm(name: &str, image: &DynamicImage) -> String {
+    let file_name = BASE64_STANDARD.encode(name);
+    let file_contents = BASE64_STANDARD.encode(png(image));
+    let (width, height) = (image.width(), image.height());
+
+    format!("\x1b]1337;File=name={file_name};inline=1;width={width}px;height={height}px:{file_contents}\x07")
+}
+
+fn kitty(image: &DynamicImage) -> String {
+    let data = BASE64_STANDARD.encode(png(image));
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let more = u8::from(i + 1 < chunks.len());
+            let keys = if i == 0 {
+                format!("a=T,f=100,m={more}")
+            } else {
+                format!("m={more}")
+            };
+
+            format!("\x1b_G{keys};{}\x1b\\", String::from_utf8_lossy(chunk))
+        })
+        .collect()
+}
+
+fn sixel(image: &DynamicImage) -> String {
+    let rgb = image.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    let levels = SIXEL_LEVELS;
+    let level = |c: u8| (c as usize * (levels - 1) + 127) / 255;
+    let colour =
+        |Rgb([r, g, b]): &Rgb<u8>| level(*r) * levels * levels + level(*g) * levels + level(*b);
+
+    let mut out = format!("\x1bPq\"1;1;{width};{height}");
+
+    // the palette is given in percent
+    for i in 0..levels.pow(3) {
+        let [r, g, b] = [i / (levels * levels), i / levels % levels, i % levels]
+            .map(|l| l * 100 / (levels - 1));
+        out.push_str(&format!("#{i};2;{r};{g};{b}"));
+    }
+
+    // each band is six rows. every colour in the band is drawn over the same
+    // columns in turn, with the rows it's in set as bits of a character
+    for band in (0..height).step_by(6) {
+        let mut columns: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        for x in 0..width {
+            for row in 0..6.min(height - band) {
+                let c = colour(rgb.get_pixel(x, band + row));
+                columns.entry(c).or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << row;
+            }
+        }
+
+        for (i, (c, bits)) in columns.iter().enumerate() {
+            if i > 0 {
+                out.push('$');
+            }
+            out.push_str(&format!("#{c}"));
+            out.push_str(&run_length(bits));
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+
+    out
+}
+
+/// Sixel characters, with repeats shortened to `!<count><char>`
+fn run_length(bits: &[u8]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < bits.len() {
+        let run = bits[i..].iter().take_while(|b| **b == bits[i]).count();
+        let c = (63 + bits[i]) as char;
+        if run > 3 {
+            out.push_str(&format!("!{run}{c}"));
+        } else {
+            out.extend(std::iter::repeat_n(c, run));
+        }
+        i += run;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kitty, sixel};
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    #[test]
+    fn sixel_draws_each_colour_in_its_columns() {
+        let mut image = RgbImage::from_pixel(5, 6, Rgb([255, 0, 0]));
+        image.put_pixel(4, 0, Rgb([0, 0, 255]));
+
+        let encoded = sixel(&DynamicImage::ImageRgb8(image));
+
+        assert!(encoded.starts_with("\x1bPq\"1;1;5;6"));
+        // blue in the top row of the last column, red everywhere else
+        assert!(encoded.ends_with("#5!4?@$#180!4~}-\x1b\\"));
+    }
+
+    #[test]
+    fn kitty_splits_big_images_into_chunks() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(300, 300, |x, y| {
+            Rgb([x as u8, y as u8, (x ^ y) as u8])
+        }));
+
+        let encoded = kitty(&image);
+        let chunks: Vec<_> = encoded.split("\x1b\\").filter(|c| !c.is_empty()).collect();
+
+        assert!(chunks.len() > 1);
+        assert!(chunks[0].starts_with("\x1b_Ga=T,f=100,m=1;"));
+        assert!(chunks[chunks.len() - 1].starts_with("\x1b_Gm=0;"));
+    }
+}